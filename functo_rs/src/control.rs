@@ -1,22 +1,28 @@
 //! Control functors are special case of [data functors][`crate::data`], which can take [`FnOnce`] as continuations.
 
 use crate::data;
+pub use crate::fail::FailInfo;
 pub use crate::impls::*;
+use crate::monoid::{Monoid, Semigroup};
+use crate::reader::ReaderFn;
+use crate::state::StateFn;
+use std::convert::Infallible;
+use std::ops::ControlFlow;
 
 /// A type-level label to force qualified_do to use `control::Functor`-hierarchy.
 pub struct AsControl<F>(std::marker::PhantomData<F>);
 
 pub trait Functor: data::Functor {
-    fn fmap<A, B, F>(f: F, fa: Self::Container<A>) -> Self::Container<B>
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, B>
     where
-        F: FnOnce(A) -> B;
+        F: FnOnce(A) -> B + 'a;
 }
 
 impl<F: Functor> AsControl<F> {
     #[inline(always)]
-    pub fn fmap<A, B, G>(f: G, fa: F::Container<A>) -> F::Container<B>
+    pub fn fmap<'a, A: 'a, B: 'a, G>(f: G, fa: F::Container<'a, A>) -> F::Container<'a, B>
     where
-        G: FnOnce(A) -> B,
+        G: FnOnce(A) -> B + 'a,
     {
         <F as Functor>::fmap(f, fa)
     }
@@ -24,9 +30,9 @@ impl<F: Functor> AsControl<F> {
 
 impl Functor for Identity {
     #[inline(always)]
-    fn fmap<A, B, F>(f: F, a: A) -> B
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, a: A) -> B
     where
-        F: FnOnce(A) -> B,
+        F: FnOnce(A) -> B + 'a,
     {
         f(a)
     }
@@ -34,9 +40,9 @@ impl Functor for Identity {
 
 impl Functor for OptionFunctor {
     #[inline(always)]
-    fn fmap<A, B, F>(f: F, fa: Self::Container<A>) -> Self::Container<B>
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, B>
     where
-        F: FnOnce(A) -> B,
+        F: FnOnce(A) -> B + 'a,
     {
         fa.map(f)
     }
@@ -44,87 +50,219 @@ impl Functor for OptionFunctor {
 
 impl<E> Functor for ResultFunctor<E> {
     #[inline(always)]
-    fn fmap<A, B, F>(f: F, fa: Self::Container<A>) -> Self::Container<B>
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, B>
     where
-        F: FnOnce(A) -> B,
+        F: FnOnce(A) -> B + 'a,
     {
         fa.map(f)
     }
 }
 
+impl<E> Functor for CheckedResult<E> {
+    #[inline(always)]
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, B>
+    where
+        F: FnOnce(A) -> B + 'a,
+    {
+        fa.map(f)
+    }
+}
+
+impl<E> Functor for Validation<E> {
+    #[inline(always)]
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, B>
+    where
+        F: FnOnce(A) -> B + 'a,
+    {
+        fa.map(f)
+    }
+}
+
+impl<W> Functor for Writer<W> {
+    #[inline(always)]
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, (a, w): Self::Container<'a, A>) -> Self::Container<'a, B>
+    where
+        F: FnOnce(A) -> B + 'a,
+    {
+        (f(a), w)
+    }
+}
+
+impl<R: 'static> Functor for Reader<R> {
+    #[inline(always)]
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, m: Self::Container<'a, A>) -> Self::Container<'a, B>
+    where
+        F: FnOnce(A) -> B + 'a,
+    {
+        Box::new(move |r: &R| f(m(r)))
+    }
+}
+
+impl<S: 'static> Functor for State<S> {
+    #[inline(always)]
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, m: Self::Container<'a, A>) -> Self::Container<'a, B>
+    where
+        F: FnOnce(A) -> B + 'a,
+    {
+        Box::new(move |s: S| {
+            let (a, s) = m(s);
+            (f(a), s)
+        })
+    }
+}
+
 pub trait Pointed: Functor + data::Pointed {
-    fn pure<A>(a: A) -> Self::Container<A>;
+    fn pure<'a, A: 'a>(a: A) -> Self::Container<'a, A>;
 }
 
 impl<F: Pointed> AsControl<F> {
     #[inline(always)]
-    pub fn pure<A>(a: A) -> F::Container<A> {
+    pub fn pure<'a, A: 'a>(a: A) -> F::Container<'a, A> {
         <F as Pointed>::pure(a)
     }
 }
 
 impl Pointed for Identity {
     #[inline(always)]
-    fn pure<A>(a: A) -> A {
+    fn pure<'a, A: 'a>(a: A) -> A {
         a
     }
 }
 
 impl Pointed for OptionFunctor {
     #[inline(always)]
-    fn pure<A>(a: A) -> Option<A> {
+    fn pure<'a, A: 'a>(a: A) -> Option<A> {
         Some(a)
     }
 }
 
 impl<E> Pointed for ResultFunctor<E> {
     #[inline(always)]
-    fn pure<A>(a: A) -> Result<A, E> {
+    fn pure<'a, A: 'a>(a: A) -> Result<A, E> {
         Ok(a)
     }
 }
 
+impl<E> Pointed for CheckedResult<E> {
+    #[inline(always)]
+    fn pure<'a, A: 'a>(a: A) -> Result<A, E> {
+        Ok(a)
+    }
+}
+
+impl<E> Pointed for Validation<E> {
+    #[inline(always)]
+    fn pure<'a, A: 'a>(a: A) -> Result<A, E> {
+        Ok(a)
+    }
+}
+
+impl<W: Monoid> Pointed for Writer<W> {
+    #[inline(always)]
+    fn pure<'a, A: 'a>(a: A) -> (A, W) {
+        (a, W::empty())
+    }
+}
+
+impl<R: 'static> Pointed for Reader<R> {
+    #[inline(always)]
+    fn pure<'a, A: 'a>(a: A) -> ReaderFn<'a, R, A> {
+        Box::new(move |_: &R| a)
+    }
+}
+
+impl<S: 'static> Pointed for State<S> {
+    #[inline(always)]
+    fn pure<'a, A: 'a>(a: A) -> StateFn<'a, S, A> {
+        Box::new(move |s: S| (a, s))
+    }
+}
+
 pub trait Apply: Functor + data::Apply {
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
         f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        F: FnOnce(A, B) -> C;
+        F: FnOnce(A, B) -> C + 'a;
 
     #[inline(always)]
-    fn ap<A, B, F>(ff: Self::Container<F>, fa: Self::Container<A>) -> Self::Container<B>
+    fn ap<'a, A: 'a, B: 'a, F>(
+        ff: Self::Container<'a, F>,
+        fa: Self::Container<'a, A>,
+    ) -> Self::Container<'a, B>
     where
-        F: FnOnce(A) -> B,
+        F: FnOnce(A) -> B + 'a,
     {
         <Self as Apply>::zip_with(|f, a| f(a), ff, fa)
     }
+
+    /// Sequences two effects, keeping the result of the second (`*>` in Haskell).
+    #[inline(always)]
+    fn then<'a, A: 'a, B: 'a>(
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, B> {
+        <Self as Apply>::zip_with(|_, b| b, fa, fb)
+    }
+
+    /// Sequences two effects, keeping the result of the first (`<*` in Haskell).
+    #[inline(always)]
+    fn then_left<'a, A: 'a, B: 'a>(
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, A> {
+        <Self as Apply>::zip_with(|a, _| a, fa, fb)
+    }
 }
 
 impl<F: Apply> AsControl<F> {
     #[inline(always)]
-    pub fn zip_with<A, B, C, G>(f: G, fa: F::Container<A>, fb: F::Container<B>) -> F::Container<C>
+    pub fn zip_with<'a, A: 'a, B: 'a, C: 'a, G>(
+        f: G,
+        fa: F::Container<'a, A>,
+        fb: F::Container<'a, B>,
+    ) -> F::Container<'a, C>
     where
-        G: FnOnce(A, B) -> C,
+        G: FnOnce(A, B) -> C + 'a,
     {
         <F as Apply>::zip_with(f, fa, fb)
     }
 
     #[inline(always)]
-    pub fn ap<A, B, G>(ff: F::Container<G>, fa: F::Container<A>) -> F::Container<B>
+    pub fn ap<'a, A: 'a, B: 'a, G>(
+        ff: F::Container<'a, G>,
+        fa: F::Container<'a, A>,
+    ) -> F::Container<'a, B>
     where
-        G: FnOnce(A) -> B,
+        G: FnOnce(A) -> B + 'a,
     {
         <F as Apply>::ap(ff, fa)
     }
+
+    #[inline(always)]
+    pub fn then<'a, A: 'a, B: 'a>(
+        fa: F::Container<'a, A>,
+        fb: F::Container<'a, B>,
+    ) -> F::Container<'a, B> {
+        <F as Apply>::then(fa, fb)
+    }
+
+    #[inline(always)]
+    pub fn then_left<'a, A: 'a, B: 'a>(
+        fa: F::Container<'a, A>,
+        fb: F::Container<'a, B>,
+    ) -> F::Container<'a, A> {
+        <F as Apply>::then_left(fa, fb)
+    }
 }
 
 impl Apply for Identity {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(f: F, a: A, b: B) -> C
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(f: F, a: A, b: B) -> C
     where
-        F: FnOnce(A, B) -> C,
+        F: FnOnce(A, B) -> C + 'a,
     {
         f(a, b)
     }
@@ -132,13 +270,13 @@ impl Apply for Identity {
 
 impl Apply for OptionFunctor {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
         f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        F: FnOnce(A, B) -> C,
+        F: FnOnce(A, B) -> C + 'a,
     {
         fa.zip(fb).map(|(a, b)| f(a, b))
     }
@@ -146,49 +284,186 @@ impl Apply for OptionFunctor {
 
 impl<E> Apply for ResultFunctor<E> {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
+        f: F,
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        F: FnOnce(A, B) -> C + 'a,
+    {
+        fa.and_then(|a| fb.map(|b| f(a, b)))
+    }
+}
+
+impl<E> Apply for CheckedResult<E> {
+    #[inline(always)]
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
         f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        F: FnOnce(A, B) -> C,
+        F: FnOnce(A, B) -> C + 'a,
     {
         fa.and_then(|a| fb.map(|b| f(a, b)))
     }
 }
 
+/// Combines the errors of both sides if both fail.
+impl<E: Semigroup> Apply for Validation<E> {
+    #[inline(always)]
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
+        f: F,
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        F: FnOnce(A, B) -> C + 'a,
+    {
+        match (fa, fb) {
+            (Ok(a), Ok(b)) => Ok(f(a, b)),
+            (Err(e1), Err(e2)) => Err(e1.combine(e2)),
+            (Err(e), Ok(_)) | (Ok(_), Err(e)) => Err(e),
+        }
+    }
+}
+
+impl<W: Semigroup> Apply for Writer<W> {
+    #[inline(always)]
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
+        f: F,
+        (a, w1): Self::Container<'a, A>,
+        (b, w2): Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        F: FnOnce(A, B) -> C + 'a,
+    {
+        (f(a, b), w1.combine(w2))
+    }
+}
+
+impl<R: 'static> Apply for Reader<R> {
+    #[inline(always)]
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
+        f: F,
+        m: Self::Container<'a, A>,
+        n: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        F: FnOnce(A, B) -> C + 'a,
+    {
+        Box::new(move |r: &R| f(m(r), n(r)))
+    }
+}
+
+impl<S: 'static> Apply for State<S> {
+    #[inline(always)]
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
+        f: F,
+        m: Self::Container<'a, A>,
+        n: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        F: FnOnce(A, B) -> C + 'a,
+    {
+        Box::new(move |s: S| {
+            let (a, s) = m(s);
+            let (b, s) = n(s);
+            (f(a, b), s)
+        })
+    }
+}
+
 pub trait Monad: Apply + Pointed {
-    fn and_then<A, B, F>(fa: Self::Container<A>, f: F) -> Self::Container<B>
+    fn and_then<'a, A: 'a, B: 'a, F>(fa: Self::Container<'a, A>, f: F) -> Self::Container<'a, B>
     where
-        F: FnOnce(A) -> Self::Container<B>;
+        F: FnOnce(A) -> Self::Container<'a, B> + 'a;
 
     #[inline(always)]
-    fn flatten<A>(ffa: Self::Container<Self::Container<A>>) -> Self::Container<A> {
+    fn flatten<'a, A: 'a>(
+        ffa: Self::Container<'a, Self::Container<'a, A>>,
+    ) -> Self::Container<'a, A> {
         <Self as Monad>::and_then(ffa, |fa| fa)
     }
+
+    /// Runs the effect for each element in order, discarding the results.
+    ///
+    /// By default, `f` is applied to every element up front, and the effects are sequenced with `then`;
+    /// short-circuiting monads override it to stop at the first failure.
+    #[inline(always)]
+    fn for_each<'a, I, F>(iter: I, mut f: F) -> Self::Container<'a, ()>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item) -> Self::Container<'a, ()>,
+    {
+        iter.into_iter()
+            .fold(<Self as Pointed>::pure(()), |acc, a| {
+                <Self as Apply>::then(acc, f(a))
+            })
+    }
+
+    /// Runs the effect for each element in order, collecting the results.
+    ///
+    /// As with [`Monad::for_each`], the effects are built up front and joined with `zip_with` by default.
+    #[inline(always)]
+    fn traverse_iter<'a, I, B: 'a, F>(iter: I, mut f: F) -> Self::Container<'a, Vec<B>>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item) -> Self::Container<'a, B>,
+    {
+        iter.into_iter()
+            .fold(<Self as Pointed>::pure(Vec::new()), |acc, a| {
+                <Self as Apply>::zip_with(
+                    |mut bs: Vec<B>, b| {
+                        bs.push(b);
+                        bs
+                    },
+                    acc,
+                    f(a),
+                )
+            })
+    }
 }
 
 impl<F: Monad> AsControl<F> {
     #[inline(always)]
-    pub fn and_then<A, B, G>(fa: F::Container<A>, f: G) -> F::Container<B>
+    pub fn and_then<'a, A: 'a, B: 'a, G>(fa: F::Container<'a, A>, f: G) -> F::Container<'a, B>
     where
-        G: FnOnce(A) -> F::Container<B>,
+        G: FnOnce(A) -> F::Container<'a, B> + 'a,
     {
         <F as Monad>::and_then(fa, f)
     }
 
     #[inline(always)]
-    pub fn flatten<A>(ffa: F::Container<F::Container<A>>) -> F::Container<A> {
+    pub fn flatten<'a, A: 'a>(ffa: F::Container<'a, F::Container<'a, A>>) -> F::Container<'a, A> {
         <F as Monad>::flatten(ffa)
     }
+
+    #[inline(always)]
+    pub fn for_each<'a, I, G>(iter: I, f: G) -> F::Container<'a, ()>
+    where
+        I: IntoIterator,
+        G: FnMut(I::Item) -> F::Container<'a, ()>,
+    {
+        <F as Monad>::for_each(iter, f)
+    }
+
+    #[inline(always)]
+    pub fn traverse_iter<'a, I, B: 'a, G>(iter: I, f: G) -> F::Container<'a, Vec<B>>
+    where
+        I: IntoIterator,
+        G: FnMut(I::Item) -> F::Container<'a, B>,
+    {
+        <F as Monad>::traverse_iter(iter, f)
+    }
 }
 
 impl Monad for Identity {
     #[inline(always)]
-    fn and_then<A, B, F>(fa: Self::Container<A>, f: F) -> Self::Container<B>
+    fn and_then<'a, A: 'a, B: 'a, F>(fa: Self::Container<'a, A>, f: F) -> Self::Container<'a, B>
     where
-        F: FnOnce(A) -> Self::Container<B>,
+        F: FnOnce(A) -> Self::Container<'a, B> + 'a,
     {
         f(fa)
     }
@@ -196,55 +471,567 @@ impl Monad for Identity {
 
 impl Monad for OptionFunctor {
     #[inline(always)]
-    fn and_then<A, B, F>(fa: Self::Container<A>, f: F) -> Self::Container<B>
+    fn and_then<'a, A: 'a, B: 'a, F>(fa: Self::Container<'a, A>, f: F) -> Self::Container<'a, B>
     where
-        F: FnOnce(A) -> Self::Container<B>,
+        F: FnOnce(A) -> Self::Container<'a, B> + 'a,
     {
         fa.and_then(f)
     }
+
+    /// Stops at the first failure.
+    #[inline(always)]
+    fn for_each<'a, I, F>(iter: I, f: F) -> Self::Container<'a, ()>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item) -> Self::Container<'a, ()>,
+    {
+        iter.into_iter().try_for_each(f)
+    }
+
+    /// Stops at the first failure.
+    #[inline(always)]
+    fn traverse_iter<'a, I, B: 'a, F>(iter: I, f: F) -> Self::Container<'a, Vec<B>>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item) -> Self::Container<'a, B>,
+    {
+        iter.into_iter().map(f).collect()
+    }
 }
 
 impl<E> Monad for ResultFunctor<E> {
     #[inline(always)]
-    fn and_then<A, B, F>(fa: Self::Container<A>, f: F) -> Self::Container<B>
+    fn and_then<'a, A: 'a, B: 'a, F>(fa: Self::Container<'a, A>, f: F) -> Self::Container<'a, B>
     where
-        F: FnOnce(A) -> Self::Container<B>,
+        F: FnOnce(A) -> Self::Container<'a, B> + 'a,
     {
         fa.and_then(f)
     }
+
+    /// Stops at the first failure.
+    #[inline(always)]
+    fn for_each<'a, I, F>(iter: I, f: F) -> Self::Container<'a, ()>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item) -> Self::Container<'a, ()>,
+    {
+        iter.into_iter().try_for_each(f)
+    }
+
+    /// Stops at the first failure.
+    #[inline(always)]
+    fn traverse_iter<'a, I, B: 'a, F>(iter: I, f: F) -> Self::Container<'a, Vec<B>>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item) -> Self::Container<'a, B>,
+    {
+        iter.into_iter().map(f).collect()
+    }
+}
+
+impl<E> Monad for CheckedResult<E> {
+    #[inline(always)]
+    fn and_then<'a, A: 'a, B: 'a, F>(fa: Self::Container<'a, A>, f: F) -> Self::Container<'a, B>
+    where
+        F: FnOnce(A) -> Self::Container<'a, B> + 'a,
+    {
+        fa.and_then(f)
+    }
+
+    /// Stops at the first failure.
+    #[inline(always)]
+    fn for_each<'a, I, F>(iter: I, f: F) -> Self::Container<'a, ()>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item) -> Self::Container<'a, ()>,
+    {
+        iter.into_iter().try_for_each(f)
+    }
+
+    /// Stops at the first failure.
+    #[inline(always)]
+    fn traverse_iter<'a, I, B: 'a, F>(iter: I, f: F) -> Self::Container<'a, Vec<B>>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item) -> Self::Container<'a, B>,
+    {
+        iter.into_iter().map(f).collect()
+    }
+}
+
+impl<W: Monoid> Monad for Writer<W> {
+    #[inline(always)]
+    fn and_then<'a, A: 'a, B: 'a, F>(
+        (a, w1): Self::Container<'a, A>,
+        f: F,
+    ) -> Self::Container<'a, B>
+    where
+        F: FnOnce(A) -> Self::Container<'a, B> + 'a,
+    {
+        let (b, w2) = f(a);
+        (b, w1.combine(w2))
+    }
+}
+
+impl<R: 'static> Monad for Reader<R> {
+    #[inline(always)]
+    fn and_then<'a, A: 'a, B: 'a, F>(m: Self::Container<'a, A>, f: F) -> Self::Container<'a, B>
+    where
+        F: FnOnce(A) -> Self::Container<'a, B> + 'a,
+    {
+        Box::new(move |r: &R| f(m(r))(r))
+    }
+}
+
+impl<S: 'static> Monad for State<S> {
+    #[inline(always)]
+    fn and_then<'a, A: 'a, B: 'a, F>(m: Self::Container<'a, A>, f: F) -> Self::Container<'a, B>
+    where
+        F: FnOnce(A) -> Self::Container<'a, B> + 'a,
+    {
+        Box::new(move |s: S| {
+            let (a, s) = m(s);
+            f(a)(s)
+        })
+    }
 }
 
 pub trait MonadFail: Monad {
-    fn fail<A>(msg: &str) -> Self::Container<A>;
+    fn fail<'a, A: 'a>(msg: &str) -> Self::Container<'a, A>;
+
+    /// Fails with structured information about the failed binding.
+    /// `qdo` calls it when a refutable pattern doesn't match; by default, it fails with the rendered message.
+    #[inline(always)]
+    fn fail_with<'a, A: 'a>(info: FailInfo) -> Self::Container<'a, A> {
+        Self::fail(&info.to_string())
+    }
 }
 
 impl<T: MonadFail> AsControl<T> {
     #[inline(always)]
-    pub fn fail<A>(msg: &str) -> T::Container<A> {
+    pub fn fail<'a, A: 'a>(msg: &str) -> T::Container<'a, A> {
         <T as MonadFail>::fail(msg)
     }
+
+    #[inline(always)]
+    pub fn fail_with<'a, A: 'a>(info: FailInfo) -> T::Container<'a, A> {
+        <T as MonadFail>::fail_with(info)
+    }
 }
 
 impl MonadFail for OptionFunctor {
     #[inline(always)]
-    fn fail<A>(_msg: &str) -> Option<A> {
+    fn fail<'a, A: 'a>(_msg: &str) -> Option<A> {
         None
     }
 }
 
 impl<E: From<String>> MonadFail for ResultFunctor<E> {
     #[inline(always)]
-    fn fail<A>(msg: &str) -> Result<A, E> {
+    fn fail<'a, A: 'a>(msg: &str) -> Result<A, E> {
         Err(msg.to_string().into())
     }
 }
 
+/// [`CheckedResult`] is not a [`MonadFail`], as it cannot fail with a bare message;
+/// `qdo` only needs `fail_with`, which keeps the structured information.
+impl<E: From<FailInfo>> AsControl<CheckedResult<E>> {
+    #[inline(always)]
+    pub fn fail_with<'a, A: 'a>(info: FailInfo) -> Result<A, E> {
+        Err(info.into())
+    }
+}
+
+/// Monads with a typed error channel.
+pub trait MonadError: Monad {
+    type Error;
+
+    /// Fails with the error.
+    fn throw_error<'a, A: 'a>(e: Self::Error) -> Self::Container<'a, A>;
+
+    /// Recovers from the error with the handler, if any.
+    fn catch_error<'a, A: 'a, F>(fa: Self::Container<'a, A>, handler: F) -> Self::Container<'a, A>
+    where
+        F: FnOnce(Self::Error) -> Self::Container<'a, A> + 'a;
+}
+
+impl<F: MonadError> AsControl<F> {
+    #[inline(always)]
+    pub fn throw_error<'a, A: 'a>(e: F::Error) -> F::Container<'a, A> {
+        <F as MonadError>::throw_error(e)
+    }
+
+    #[inline(always)]
+    pub fn catch_error<'a, A: 'a, G>(fa: F::Container<'a, A>, handler: G) -> F::Container<'a, A>
+    where
+        G: FnOnce(F::Error) -> F::Container<'a, A> + 'a,
+    {
+        <F as MonadError>::catch_error(fa, handler)
+    }
+}
+
+impl MonadError for OptionFunctor {
+    type Error = ();
+
+    #[inline(always)]
+    fn throw_error<'a, A: 'a>(_: ()) -> Option<A> {
+        None
+    }
+
+    #[inline(always)]
+    fn catch_error<'a, A: 'a, F>(fa: Option<A>, handler: F) -> Option<A>
+    where
+        F: FnOnce(()) -> Option<A> + 'a,
+    {
+        fa.or_else(|| handler(()))
+    }
+}
+
+impl<E> MonadError for ResultFunctor<E> {
+    type Error = E;
+
+    #[inline(always)]
+    fn throw_error<'a, A: 'a>(e: E) -> Result<A, E> {
+        Err(e)
+    }
+
+    #[inline(always)]
+    fn catch_error<'a, A: 'a, F>(fa: Result<A, E>, handler: F) -> Result<A, E>
+    where
+        F: FnOnce(E) -> Result<A, E> + 'a,
+    {
+        fa.or_else(handler)
+    }
+}
+
+impl<E> MonadError for CheckedResult<E> {
+    type Error = E;
+
+    #[inline(always)]
+    fn throw_error<'a, A: 'a>(e: E) -> Result<A, E> {
+        Err(e)
+    }
+
+    #[inline(always)]
+    fn catch_error<'a, A: 'a, F>(fa: Result<A, E>, handler: F) -> Result<A, E>
+    where
+        F: FnOnce(E) -> Result<A, E> + 'a,
+    {
+        fa.or_else(handler)
+    }
+}
+
+/// Monads accumulating an output alongside the values.
+pub trait MonadWriter: Monad {
+    type Output;
+
+    /// Appends the output.
+    fn tell<'a>(w: Self::Output) -> Self::Container<'a, ()>;
+
+    /// Exposes the output of the computation alongside its value.
+    fn listen<'a, A: 'a>(fa: Self::Container<'a, A>) -> Self::Container<'a, (A, Self::Output)>;
+
+    /// Modifies the output of the computation with `f`.
+    fn censor<'a, A: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, A>
+    where
+        F: FnOnce(Self::Output) -> Self::Output + 'a;
+}
+
+impl<F: MonadWriter> AsControl<F> {
+    #[inline(always)]
+    pub fn tell<'a>(w: F::Output) -> F::Container<'a, ()> {
+        <F as MonadWriter>::tell(w)
+    }
+
+    #[inline(always)]
+    pub fn listen<'a, A: 'a>(fa: F::Container<'a, A>) -> F::Container<'a, (A, F::Output)> {
+        <F as MonadWriter>::listen(fa)
+    }
+
+    #[inline(always)]
+    pub fn censor<'a, A: 'a, G>(f: G, fa: F::Container<'a, A>) -> F::Container<'a, A>
+    where
+        G: FnOnce(F::Output) -> F::Output + 'a,
+    {
+        <F as MonadWriter>::censor(f, fa)
+    }
+}
+
+impl<W: Monoid + Clone> MonadWriter for Writer<W> {
+    type Output = W;
+
+    #[inline(always)]
+    fn tell<'a>(w: W) -> Self::Container<'a, ()> {
+        ((), w)
+    }
+
+    #[inline(always)]
+    fn listen<'a, A: 'a>((a, w): (A, W)) -> ((A, W), W) {
+        ((a, w.clone()), w)
+    }
+
+    #[inline(always)]
+    fn censor<'a, A: 'a, F>(f: F, (a, w): (A, W)) -> (A, W)
+    where
+        F: FnOnce(W) -> W + 'a,
+    {
+        (a, f(w))
+    }
+}
+
+/// Monads reading a shared environment.
+pub trait MonadReader: Monad {
+    type Env;
+
+    /// Retrieves a function of the environment.
+    fn asks<'a, A: 'a, F>(f: F) -> Self::Container<'a, A>
+    where
+        F: FnOnce(&Self::Env) -> A + 'a;
+
+    /// Retrieves the environment.
+    #[inline(always)]
+    fn ask<'a>() -> Self::Container<'a, Self::Env>
+    where
+        Self::Env: Clone + 'a,
+    {
+        Self::asks(Self::Env::clone)
+    }
+
+    /// Runs the computation in the environment modified by `f`.
+    fn local<'a, A: 'a, F>(f: F, m: Self::Container<'a, A>) -> Self::Container<'a, A>
+    where
+        F: FnOnce(&Self::Env) -> Self::Env + 'a;
+}
+
+impl<F: MonadReader> AsControl<F> {
+    #[inline(always)]
+    pub fn asks<'a, A: 'a, G>(f: G) -> F::Container<'a, A>
+    where
+        G: FnOnce(&F::Env) -> A + 'a,
+    {
+        <F as MonadReader>::asks(f)
+    }
+
+    #[inline(always)]
+    pub fn ask<'a>() -> F::Container<'a, F::Env>
+    where
+        F::Env: Clone + 'a,
+    {
+        <F as MonadReader>::ask()
+    }
+
+    #[inline(always)]
+    pub fn local<'a, A: 'a, G>(f: G, m: F::Container<'a, A>) -> F::Container<'a, A>
+    where
+        G: FnOnce(&F::Env) -> F::Env + 'a,
+    {
+        <F as MonadReader>::local(f, m)
+    }
+}
+
+impl<R: 'static> MonadReader for Reader<R> {
+    type Env = R;
+
+    #[inline(always)]
+    fn asks<'a, A: 'a, F>(f: F) -> ReaderFn<'a, R, A>
+    where
+        F: FnOnce(&R) -> A + 'a,
+    {
+        Box::new(f)
+    }
+
+    #[inline(always)]
+    fn local<'a, A: 'a, F>(f: F, m: ReaderFn<'a, R, A>) -> ReaderFn<'a, R, A>
+    where
+        F: FnOnce(&R) -> R + 'a,
+    {
+        Box::new(move |r: &R| m(&f(r)))
+    }
+}
+
+/// Monads threading a state through computations.
+pub trait MonadState: Monad {
+    type State;
+
+    /// Retrieves a function of the current state.
+    fn gets<'a, A: 'a, F>(f: F) -> Self::Container<'a, A>
+    where
+        F: FnOnce(&Self::State) -> A + 'a;
+
+    /// Retrieves the current state.
+    #[inline(always)]
+    fn get<'a>() -> Self::Container<'a, Self::State>
+    where
+        Self::State: Clone + 'a,
+    {
+        Self::gets(Self::State::clone)
+    }
+
+    /// Replaces the state.
+    fn put<'a>(s: Self::State) -> Self::Container<'a, ()>;
+
+    /// Modifies the state with `f`.
+    fn modify<'a, F>(f: F) -> Self::Container<'a, ()>
+    where
+        F: FnOnce(Self::State) -> Self::State + 'a;
+}
+
+impl<F: MonadState> AsControl<F> {
+    #[inline(always)]
+    pub fn gets<'a, A: 'a, G>(f: G) -> F::Container<'a, A>
+    where
+        G: FnOnce(&F::State) -> A + 'a,
+    {
+        <F as MonadState>::gets(f)
+    }
+
+    #[inline(always)]
+    pub fn get<'a>() -> F::Container<'a, F::State>
+    where
+        F::State: Clone + 'a,
+    {
+        <F as MonadState>::get()
+    }
+
+    #[inline(always)]
+    pub fn put<'a>(s: F::State) -> F::Container<'a, ()> {
+        <F as MonadState>::put(s)
+    }
+
+    #[inline(always)]
+    pub fn modify<'a, G>(f: G) -> F::Container<'a, ()>
+    where
+        G: FnOnce(F::State) -> F::State + 'a,
+    {
+        <F as MonadState>::modify(f)
+    }
+}
+
+impl<S: 'static> MonadState for State<S> {
+    type State = S;
+
+    #[inline(always)]
+    fn gets<'a, A: 'a, F>(f: F) -> StateFn<'a, S, A>
+    where
+        F: FnOnce(&S) -> A + 'a,
+    {
+        Box::new(move |s: S| (f(&s), s))
+    }
+
+    #[inline(always)]
+    fn put<'a>(s: S) -> Self::Container<'a, ()> {
+        Box::new(move |_: S| ((), s))
+    }
+
+    #[inline(always)]
+    fn modify<'a, F>(f: F) -> StateFn<'a, S, ()>
+    where
+        F: FnOnce(S) -> S + 'a,
+    {
+        Box::new(move |s: S| ((), f(s)))
+    }
+}
+
+/// Monads which can short-circuit the rest of computation, just as `?`-operator does.
+///
+/// This allows `qdo` blocks to be evaluated in direct style, without nested closures.
+pub trait ShortCircuit: Monad {
+    /// The value carried on short-circuiting.
+    type Residual;
+
+    /// Either continues with the value or short-circuits with the residual.
+    fn branch<'a, A: 'a>(fa: Self::Container<'a, A>) -> ControlFlow<Self::Residual, A>;
+
+    /// Reconstructs a short-circuited container from the residual.
+    fn from_residual<'a, A: 'a>(r: Self::Residual) -> Self::Container<'a, A>;
+}
+
+impl<F: ShortCircuit> AsControl<F> {
+    #[inline(always)]
+    pub fn branch<'a, A: 'a>(fa: F::Container<'a, A>) -> ControlFlow<F::Residual, A> {
+        <F as ShortCircuit>::branch(fa)
+    }
+
+    #[inline(always)]
+    pub fn from_residual<'a, A: 'a>(r: F::Residual) -> F::Container<'a, A> {
+        <F as ShortCircuit>::from_residual(r)
+    }
+}
+
+impl ShortCircuit for Identity {
+    type Residual = Infallible;
+
+    #[inline(always)]
+    fn branch<'a, A: 'a>(fa: A) -> ControlFlow<Infallible, A> {
+        ControlFlow::Continue(fa)
+    }
+
+    #[inline(always)]
+    fn from_residual<'a, A: 'a>(r: Infallible) -> A {
+        match r {}
+    }
+}
+
+impl ShortCircuit for OptionFunctor {
+    type Residual = ();
+
+    #[inline(always)]
+    fn branch<'a, A: 'a>(fa: Option<A>) -> ControlFlow<(), A> {
+        match fa {
+            Some(a) => ControlFlow::Continue(a),
+            None => ControlFlow::Break(()),
+        }
+    }
+
+    #[inline(always)]
+    fn from_residual<'a, A: 'a>(_: ()) -> Option<A> {
+        None
+    }
+}
+
+impl<E> ShortCircuit for ResultFunctor<E> {
+    type Residual = E;
+
+    #[inline(always)]
+    fn branch<'a, A: 'a>(fa: Result<A, E>) -> ControlFlow<E, A> {
+        match fa {
+            Ok(a) => ControlFlow::Continue(a),
+            Err(e) => ControlFlow::Break(e),
+        }
+    }
+
+    #[inline(always)]
+    fn from_residual<'a, A: 'a>(e: E) -> Result<A, E> {
+        Err(e)
+    }
+}
+
+impl<E> ShortCircuit for CheckedResult<E> {
+    type Residual = E;
+
+    #[inline(always)]
+    fn branch<'a, A: 'a>(fa: Result<A, E>) -> ControlFlow<E, A> {
+        match fa {
+            Ok(a) => ControlFlow::Continue(a),
+            Err(e) => ControlFlow::Break(e),
+        }
+    }
+
+    #[inline(always)]
+    fn from_residual<'a, A: 'a>(e: E) -> Result<A, E> {
+        Err(e)
+    }
+}
+
 pub trait Alternative: Apply + Pointed {
-    fn empty<T>() -> Self::Container<T>;
-    fn choice<T>(a: Self::Container<T>, b: Self::Container<T>) -> Self::Container<T>;
+    fn empty<'a, T: 'a>() -> Self::Container<'a, T>;
+    fn choice<'a, T: 'a>(
+        a: Self::Container<'a, T>,
+        b: Self::Container<'a, T>,
+    ) -> Self::Container<'a, T>;
 
     #[inline(always)]
-    fn guard(p: bool) -> Self::Container<()> {
+    fn guard<'a>(p: bool) -> Self::Container<'a, ()> {
         if p {
             <Self as Pointed>::pure(())
         } else {
@@ -255,41 +1042,50 @@ pub trait Alternative: Apply + Pointed {
 
 impl<G: Alternative> AsControl<G> {
     #[inline(always)]
-    pub fn empty<T>() -> G::Container<T> {
+    pub fn empty<'a, T: 'a>() -> G::Container<'a, T> {
         G::empty()
     }
 
     #[inline(always)]
-    pub fn choice<T>(a: G::Container<T>, b: G::Container<T>) -> G::Container<T> {
+    pub fn choice<'a, T: 'a>(
+        a: G::Container<'a, T>,
+        b: G::Container<'a, T>,
+    ) -> G::Container<'a, T> {
         G::choice(a, b)
     }
 
     #[inline(always)]
-    pub fn guard(p: bool) -> G::Container<()> {
+    pub fn guard<'a>(p: bool) -> G::Container<'a, ()> {
         G::guard(p)
     }
 }
 
 impl Alternative for OptionFunctor {
     #[inline(always)]
-    fn empty<T>() -> Option<T> {
+    fn empty<'a, T: 'a>() -> Option<T> {
         None
     }
 
     #[inline(always)]
-    fn choice<T>(a: Self::Container<T>, b: Self::Container<T>) -> Self::Container<T> {
+    fn choice<'a, T: 'a>(
+        a: Self::Container<'a, T>,
+        b: Self::Container<'a, T>,
+    ) -> Self::Container<'a, T> {
         a.or(b)
     }
 }
 
 impl<E: Default> Alternative for ResultFunctor<E> {
     #[inline(always)]
-    fn empty<T>() -> Result<T, E> {
+    fn empty<'a, T: 'a>() -> Result<T, E> {
         Err(E::default())
     }
 
     #[inline(always)]
-    fn choice<T>(a: Self::Container<T>, b: Self::Container<T>) -> Self::Container<T> {
+    fn choice<'a, T: 'a>(
+        a: Self::Container<'a, T>,
+        b: Self::Container<'a, T>,
+    ) -> Self::Container<'a, T> {
         a.or(b)
     }
 }
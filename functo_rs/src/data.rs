@@ -4,6 +4,10 @@
 use std::fmt::{Debug, Formatter};
 use std::marker::PhantomData;
 
+use crate::monoid::{Monoid, Semigroup};
+use crate::reader::ReaderFn;
+use crate::state::StateFn;
+
 pub use crate::impls::*;
 
 /// A type-level label to force qualified_do to use `data::Functor`-hierarchy.
@@ -11,148 +15,222 @@ pub struct AsData<F>(PhantomData<F>);
 
 impl<G: Functor> AsData<G> {
     #[inline(always)]
-    pub fn fmap<A, B, F>(f: F, fa: G::Container<A>) -> G::Container<B>
+    pub fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: G::Container<'a, A>) -> G::Container<'a, B>
     where
-        F: FnMut(A) -> B,
+        F: FnMut(A) -> B + 'a,
     {
         G::fmap(f, fa)
     }
 }
 
 pub trait Functor {
-    type Container<T>;
+    /// The container of `T`s, which lives for `'a` if it captures continuations (e.g. [`Reader`]).
+    type Container<'a, T: 'a>;
 
-    fn fmap<A, B, F>(f: F, fa: Self::Container<A>) -> Self::Container<B>
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, B>
     where
-        F: FnMut(A) -> B;
+        F: FnMut(A) -> B + 'a;
 }
 
 impl Functor for Identity {
-    type Container<T> = T;
+    type Container<'a, T: 'a> = T;
 
     #[inline(always)]
-    fn fmap<A, B, F>(mut f: F, a: A) -> B
+    fn fmap<'a, A: 'a, B: 'a, F>(mut f: F, a: A) -> B
     where
-        F: FnMut(A) -> B,
+        F: FnMut(A) -> B + 'a,
     {
         f(a)
     }
 }
 
 impl Functor for UndetVec {
-    type Container<T> = Vec<T>;
+    type Container<'a, T: 'a> = Vec<T>;
 
     #[inline(always)]
-    fn fmap<A, B, F>(f: F, fa: Self::Container<A>) -> Self::Container<B>
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, B>
     where
-        F: FnMut(A) -> B,
+        F: FnMut(A) -> B + 'a,
     {
         fa.into_iter().map(f).collect()
     }
 }
 
 impl Functor for ZipVec {
-    type Container<T> = Vec<T>;
+    type Container<'a, T: 'a> = Vec<T>;
 
     #[inline(always)]
-    fn fmap<A, B, F>(f: F, fa: Self::Container<A>) -> Self::Container<B>
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, B>
     where
-        F: FnMut(A) -> B,
+        F: FnMut(A) -> B + 'a,
     {
         fa.into_iter().map(f).collect()
     }
 }
 
 impl Functor for OptionFunctor {
-    type Container<T> = Option<T>;
+    type Container<'a, T: 'a> = Option<T>;
 
     #[inline(always)]
-    fn fmap<A, B, F>(f: F, fa: Self::Container<A>) -> Self::Container<B>
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, B>
     where
-        F: FnMut(A) -> B,
+        F: FnMut(A) -> B + 'a,
     {
         fa.map(f)
     }
 }
 
 impl<E> Functor for ResultFunctor<E> {
-    type Container<T> = Result<T, E>;
+    type Container<'a, T: 'a> = Result<T, E>;
+
+    #[inline(always)]
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, B>
+    where
+        F: FnMut(A) -> B + 'a,
+    {
+        fa.map(f)
+    }
+}
+
+impl<E> Functor for CheckedResult<E> {
+    type Container<'a, T: 'a> = Result<T, E>;
 
     #[inline(always)]
-    fn fmap<A, B, F>(f: F, fa: Self::Container<A>) -> Self::Container<B>
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, B>
     where
-        F: FnMut(A) -> B,
+        F: FnMut(A) -> B + 'a,
     {
         fa.map(f)
     }
 }
 
 impl Functor for V2 {
-    type Container<T> = (T, T);
+    type Container<'a, T: 'a> = (T, T);
 
     #[inline(always)]
-    fn fmap<A, B, F>(mut f: F, (a1, a2): Self::Container<A>) -> Self::Container<B>
+    fn fmap<'a, A: 'a, B: 'a, F>(
+        mut f: F,
+        (a1, a2): Self::Container<'a, A>,
+    ) -> Self::Container<'a, B>
     where
-        F: FnMut(A) -> B,
+        F: FnMut(A) -> B + 'a,
     {
         (f(a1), f(a2))
     }
 }
 
 impl<const N: usize> Functor for ArrayFunctor<N> {
-    type Container<T> = [T; N];
+    type Container<'a, T: 'a> = [T; N];
+
+    #[inline(always)]
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, B>
+    where
+        F: FnMut(A) -> B + 'a,
+    {
+        fa.map(f)
+    }
+}
+
+impl<E> Functor for Validation<E> {
+    type Container<'a, T: 'a> = Result<T, E>;
 
     #[inline(always)]
-    fn fmap<A, B, F>(f: F, fa: Self::Container<A>) -> Self::Container<B>
+    fn fmap<'a, A: 'a, B: 'a, F>(f: F, fa: Self::Container<'a, A>) -> Self::Container<'a, B>
     where
-        F: FnMut(A) -> B,
+        F: FnMut(A) -> B + 'a,
     {
         fa.map(f)
     }
 }
 
+impl<W> Functor for Writer<W> {
+    type Container<'a, T: 'a> = (T, W);
+
+    #[inline(always)]
+    fn fmap<'a, A: 'a, B: 'a, F>(mut f: F, (a, w): Self::Container<'a, A>) -> Self::Container<'a, B>
+    where
+        F: FnMut(A) -> B + 'a,
+    {
+        (f(a), w)
+    }
+}
+
+impl<R: 'static> Functor for Reader<R> {
+    type Container<'a, T: 'a> = ReaderFn<'a, R, T>;
+
+    #[inline(always)]
+    fn fmap<'a, A: 'a, B: 'a, F>(mut f: F, m: Self::Container<'a, A>) -> Self::Container<'a, B>
+    where
+        F: FnMut(A) -> B + 'a,
+    {
+        Box::new(move |r: &R| f(m(r)))
+    }
+}
+
+impl<S: 'static> Functor for State<S> {
+    type Container<'a, T: 'a> = StateFn<'a, S, T>;
+
+    #[inline(always)]
+    fn fmap<'a, A: 'a, B: 'a, F>(mut f: F, m: Self::Container<'a, A>) -> Self::Container<'a, B>
+    where
+        F: FnMut(A) -> B + 'a,
+    {
+        Box::new(move |s: S| {
+            let (a, s) = m(s);
+            (f(a), s)
+        })
+    }
+}
+
 pub trait Pointed: Functor {
-    fn pure<T: Clone>(t: T) -> Self::Container<T>;
+    fn pure<'a, T: Clone + 'a>(t: T) -> Self::Container<'a, T>;
 }
 
 impl<G: Pointed> AsData<G> {
     #[inline(always)]
-    pub fn pure<T: Clone>(t: T) -> G::Container<T> {
+    pub fn pure<'a, T: Clone + 'a>(t: T) -> G::Container<'a, T> {
         G::pure(t)
     }
 }
 
 impl Pointed for Identity {
     #[inline(always)]
-    fn pure<T: Clone>(t: T) -> T {
+    fn pure<'a, T: Clone + 'a>(t: T) -> T {
         t
     }
 }
 
 impl Pointed for OptionFunctor {
     #[inline(always)]
-    fn pure<T: Clone>(t: T) -> Option<T> {
+    fn pure<'a, T: Clone + 'a>(t: T) -> Option<T> {
         Some(t)
     }
 }
 
 impl<E> Pointed for ResultFunctor<E> {
     #[inline(always)]
-    fn pure<T: Clone>(t: T) -> Result<T, E> {
+    fn pure<'a, T: Clone + 'a>(t: T) -> Result<T, E> {
+        Ok(t)
+    }
+}
+
+impl<E> Pointed for CheckedResult<E> {
+    #[inline(always)]
+    fn pure<'a, T: Clone + 'a>(t: T) -> Result<T, E> {
         Ok(t)
     }
 }
 
 impl Pointed for UndetVec {
     #[inline(always)]
-    fn pure<T: Clone>(t: T) -> Vec<T> {
+    fn pure<'a, T: Clone + 'a>(t: T) -> Vec<T> {
         vec![t]
     }
 }
 
 impl Pointed for V2 {
     #[inline(always)]
-    fn pure<T: Clone>(t: T) -> (T, T) {
+    fn pure<'a, T: Clone + 'a>(t: T) -> (T, T) {
         (t.clone(), t)
     }
 }
@@ -177,52 +255,124 @@ where
 }
 
 impl<const N: usize> Pointed for ArrayFunctor<N> {
-    fn pure<T: Clone>(t: T) -> [T; N] {
+    fn pure<'a, T: Clone + 'a>(t: T) -> [T; N] {
         unsafe_collect_array(itertools::repeat_n(t, N))
     }
 }
 
+impl<E> Pointed for Validation<E> {
+    #[inline(always)]
+    fn pure<'a, T: Clone + 'a>(t: T) -> Result<T, E> {
+        Ok(t)
+    }
+}
+
+impl<W: Monoid> Pointed for Writer<W> {
+    #[inline(always)]
+    fn pure<'a, T: Clone + 'a>(t: T) -> (T, W) {
+        (t, W::empty())
+    }
+}
+
+impl<R: 'static> Pointed for Reader<R> {
+    #[inline(always)]
+    fn pure<'a, T: Clone + 'a>(t: T) -> ReaderFn<'a, R, T> {
+        Box::new(move |_: &R| t)
+    }
+}
+
+impl<S: 'static> Pointed for State<S> {
+    #[inline(always)]
+    fn pure<'a, T: Clone + 'a>(t: T) -> StateFn<'a, S, T> {
+        Box::new(move |s: S| (t, s))
+    }
+}
+
 pub trait Apply: Functor {
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
         f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        F: FnMut(A, B) -> C;
+        F: FnMut(A, B) -> C + 'a;
 
     #[inline(always)]
-    fn ap<A, B, F>(ff: Self::Container<F>, fa: Self::Container<A>) -> Self::Container<B>
+    fn ap<'a, A: 'a, B: 'a, F>(
+        ff: Self::Container<'a, F>,
+        fa: Self::Container<'a, A>,
+    ) -> Self::Container<'a, B>
     where
-        F: FnOnce(A) -> B,
+        F: FnOnce(A) -> B + 'a,
     {
         Self::zip_with(|f, a| f(a), ff, fa)
     }
+
+    /// Sequences two effects, keeping the result of the second (`*>` in Haskell).
+    #[inline(always)]
+    fn then<'a, A: 'a, B: 'a>(
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, B> {
+        Self::zip_with(|_, b| b, fa, fb)
+    }
+
+    /// Sequences two effects, keeping the result of the first (`<*` in Haskell).
+    #[inline(always)]
+    fn then_left<'a, A: 'a, B: 'a>(
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, A> {
+        Self::zip_with(|a, _| a, fa, fb)
+    }
 }
 
 impl<G: Apply> AsData<G> {
     #[inline(always)]
-    pub fn zip_with<A, B, C, F>(f: F, fa: G::Container<A>, fb: G::Container<B>) -> G::Container<C>
+    pub fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
+        f: F,
+        fa: G::Container<'a, A>,
+        fb: G::Container<'a, B>,
+    ) -> G::Container<'a, C>
     where
-        F: FnMut(A, B) -> C,
+        F: FnMut(A, B) -> C + 'a,
     {
         G::zip_with(f, fa, fb)
     }
 
     #[inline(always)]
-    pub fn ap<A, B, F>(ff: G::Container<F>, fa: G::Container<A>) -> G::Container<B>
+    pub fn ap<'a, A: 'a, B: 'a, F>(
+        ff: G::Container<'a, F>,
+        fa: G::Container<'a, A>,
+    ) -> G::Container<'a, B>
     where
-        F: FnOnce(A) -> B,
+        F: FnOnce(A) -> B + 'a,
     {
         G::ap(ff, fa)
     }
+
+    #[inline(always)]
+    pub fn then<'a, A: 'a, B: 'a>(
+        fa: G::Container<'a, A>,
+        fb: G::Container<'a, B>,
+    ) -> G::Container<'a, B> {
+        G::then(fa, fb)
+    }
+
+    #[inline(always)]
+    pub fn then_left<'a, A: 'a, B: 'a>(
+        fa: G::Container<'a, A>,
+        fb: G::Container<'a, B>,
+    ) -> G::Container<'a, A> {
+        G::then_left(fa, fb)
+    }
 }
 
 impl Apply for Identity {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(mut f: F, a: A, b: B) -> C
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(mut f: F, a: A, b: B) -> C
     where
-        F: FnMut(A, B) -> C,
+        F: FnMut(A, B) -> C + 'a,
     {
         f(a, b)
     }
@@ -230,13 +380,13 @@ impl Apply for Identity {
 
 impl Apply for OptionFunctor {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
         mut f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        F: FnMut(A, B) -> C,
+        F: FnMut(A, B) -> C + 'a,
     {
         fa.zip(fb).map(|(a, b)| f(a, b))
     }
@@ -244,13 +394,27 @@ impl Apply for OptionFunctor {
 
 impl<E> Apply for ResultFunctor<E> {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
+        mut f: F,
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        F: FnMut(A, B) -> C + 'a,
+    {
+        fa.and_then(|a| fb.map(|b| f(a, b)))
+    }
+}
+
+impl<E> Apply for CheckedResult<E> {
+    #[inline(always)]
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
         mut f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        F: FnMut(A, B) -> C,
+        F: FnMut(A, B) -> C + 'a,
     {
         fa.and_then(|a| fb.map(|b| f(a, b)))
     }
@@ -258,13 +422,13 @@ impl<E> Apply for ResultFunctor<E> {
 
 impl Apply for ZipVec {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
         mut f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        F: FnMut(A, B) -> C,
+        F: FnMut(A, B) -> C + 'a,
     {
         fa.into_iter().zip(fb).map(|(a, b)| f(a, b)).collect()
     }
@@ -272,13 +436,13 @@ impl Apply for ZipVec {
 
 impl Apply for V2 {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
         mut f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        F: FnMut(A, B) -> C,
+        F: FnMut(A, B) -> C + 'a,
     {
         (f(fa.0, fb.0), f(fa.1, fb.1))
     }
@@ -286,24 +450,94 @@ impl Apply for V2 {
 
 impl<const N: usize> Apply for ArrayFunctor<N> {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
         mut f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        F: FnMut(A, B) -> C,
+        F: FnMut(A, B) -> C + 'a,
     {
         unsafe_collect_array(fa.into_iter().zip(fb).map(|(a, b)| f(a, b)))
     }
 }
 
+/// Combines the errors of both sides if both fail.
+impl<E: Semigroup> Apply for Validation<E> {
+    #[inline(always)]
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
+        mut f: F,
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        F: FnMut(A, B) -> C + 'a,
+    {
+        match (fa, fb) {
+            (Ok(a), Ok(b)) => Ok(f(a, b)),
+            (Err(e1), Err(e2)) => Err(e1.combine(e2)),
+            (Err(e), Ok(_)) | (Ok(_), Err(e)) => Err(e),
+        }
+    }
+}
+
+impl<W: Semigroup> Apply for Writer<W> {
+    #[inline(always)]
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
+        mut f: F,
+        (a, w1): Self::Container<'a, A>,
+        (b, w2): Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        F: FnMut(A, B) -> C + 'a,
+    {
+        (f(a, b), w1.combine(w2))
+    }
+}
+
+/// Both computations read the same environment.
+impl<R: 'static> Apply for Reader<R> {
+    #[inline(always)]
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
+        mut f: F,
+        m: Self::Container<'a, A>,
+        n: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        F: FnMut(A, B) -> C + 'a,
+    {
+        Box::new(move |r: &R| f(m(r), n(r)))
+    }
+}
+
+/// The second computation runs in the state left by the first.
+impl<S: 'static> Apply for State<S> {
+    #[inline(always)]
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
+        mut f: F,
+        m: Self::Container<'a, A>,
+        n: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        F: FnMut(A, B) -> C + 'a,
+    {
+        Box::new(move |s: S| {
+            let (a, s) = m(s);
+            let (b, s) = n(s);
+            (f(a, b), s)
+        })
+    }
+}
+
 pub trait Alternative: Apply + Pointed {
-    fn empty<T>() -> Self::Container<T>;
-    fn choice<T>(a: Self::Container<T>, b: Self::Container<T>) -> Self::Container<T>;
+    fn empty<'a, T: 'a>() -> Self::Container<'a, T>;
+    fn choice<'a, T: 'a>(
+        a: Self::Container<'a, T>,
+        b: Self::Container<'a, T>,
+    ) -> Self::Container<'a, T>;
 
     #[inline(always)]
-    fn guard(p: bool) -> Self::Container<()> {
+    fn guard<'a>(p: bool) -> Self::Container<'a, ()> {
         if p {
             Self::pure(())
         } else {
@@ -314,41 +548,50 @@ pub trait Alternative: Apply + Pointed {
 
 impl<G: Alternative> AsData<G> {
     #[inline(always)]
-    pub fn empty<T>() -> G::Container<T> {
+    pub fn empty<'a, T: 'a>() -> G::Container<'a, T> {
         G::empty()
     }
 
     #[inline(always)]
-    pub fn choice<T>(a: G::Container<T>, b: G::Container<T>) -> G::Container<T> {
+    pub fn choice<'a, T: 'a>(
+        a: G::Container<'a, T>,
+        b: G::Container<'a, T>,
+    ) -> G::Container<'a, T> {
         G::choice(a, b)
     }
 
     #[inline(always)]
-    pub fn guard(p: bool) -> G::Container<()> {
+    pub fn guard<'a>(p: bool) -> G::Container<'a, ()> {
         G::guard(p)
     }
 }
 
 impl Alternative for OptionFunctor {
     #[inline(always)]
-    fn empty<T>() -> Option<T> {
+    fn empty<'a, T: 'a>() -> Option<T> {
         None
     }
 
     #[inline(always)]
-    fn choice<T>(a: Self::Container<T>, b: Self::Container<T>) -> Self::Container<T> {
+    fn choice<'a, T: 'a>(
+        a: Self::Container<'a, T>,
+        b: Self::Container<'a, T>,
+    ) -> Self::Container<'a, T> {
         a.or(b)
     }
 }
 
 impl<E: Default> Alternative for ResultFunctor<E> {
     #[inline(always)]
-    fn empty<T>() -> Result<T, E> {
+    fn empty<'a, T: 'a>() -> Result<T, E> {
         Err(E::default())
     }
 
     #[inline(always)]
-    fn choice<T>(a: Self::Container<T>, b: Self::Container<T>) -> Self::Container<T> {
+    fn choice<'a, T: 'a>(
+        a: Self::Container<'a, T>,
+        b: Self::Container<'a, T>,
+    ) -> Self::Container<'a, T> {
         a.or(b)
     }
 }
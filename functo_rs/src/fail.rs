@@ -0,0 +1,58 @@
+//! Structured information about pattern-match failures in `qdo` blocks.
+
+use std::fmt;
+
+/// Describes which binding failed to match, as reported by `qdo` to `fail_with`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FailInfo {
+    /// The source text of the pattern, including its guard if any.
+    pub pattern: &'static str,
+    /// The source text of the expression bound to the pattern.
+    pub scrutinee: &'static str,
+    /// The file containing the binding.
+    pub file: &'static str,
+    /// The line of the binding.
+    pub line: u32,
+    /// The column of the binding.
+    pub column: u32,
+}
+
+impl From<(&'static str, &'static str, &'static str, u32, u32)> for FailInfo {
+    #[inline(always)]
+    fn from(
+        (pattern, scrutinee, file, line, column): (
+            &'static str,
+            &'static str,
+            &'static str,
+            u32,
+            u32,
+        ),
+    ) -> Self {
+        FailInfo {
+            pattern,
+            scrutinee,
+            file,
+            line,
+            column,
+        }
+    }
+}
+
+impl fmt::Display for FailInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Pattern match failed at {}:{}:{}:\n  expected: {}\n  scrutinee: {}",
+            self.file, self.line, self.column, self.pattern, self.scrutinee
+        )
+    }
+}
+
+impl std::error::Error for FailInfo {}
+
+impl From<FailInfo> for String {
+    #[inline(always)]
+    fn from(info: FailInfo) -> Self {
+        info.to_string()
+    }
+}
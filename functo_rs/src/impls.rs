@@ -14,6 +14,12 @@ pub struct ResultFunctor<E> {
     phantom: PhantomData<E>,
 }
 
+/// Like [`ResultFunctor`], but `qdo` reports pattern-match failures as structured [`FailInfo`](crate::fail::FailInfo),
+/// converted into the error with `E: From<FailInfo>` instead of being rendered as a message.
+pub struct CheckedResult<E> {
+    phantom: PhantomData<E>,
+}
+
 pub enum V2 {}
 
 pub struct Reader<R> {
@@ -24,4 +30,17 @@ pub struct State<S> {
     pub env: PhantomData<S>,
 }
 
+/// Accumulates an output of type `W` alongside the value, as in `(value, output)`.
+pub struct Writer<W> {
+    phantom: PhantomData<W>,
+}
+
+/// Like [`ResultFunctor`], but accumulates errors of all the failures in `zip_with` instead of short-circuiting.
+///
+/// Hence it is an applicative functor but deliberately not a monad:
+/// `and_then` cannot run the continuation without the value of a failed computation.
+pub struct Validation<E> {
+    phantom: PhantomData<E>,
+}
+
 pub struct ArrayFunctor<const N: usize> {}
@@ -10,3 +10,13 @@ pub mod control;
 pub mod data;
 
 pub mod nonlinear;
+
+pub mod fail;
+
+pub mod reader;
+
+pub mod state;
+
+pub mod monoid;
+
+pub mod traversable;
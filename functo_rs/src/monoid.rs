@@ -0,0 +1,176 @@
+//! Semigroups and monoids, i.e. types whose values can be combined associatively.
+
+use std::ops::{Add, Mul};
+
+/// Types with an associative binary operation (`<>` in Haskell).
+pub trait Semigroup {
+    fn combine(self, other: Self) -> Self;
+}
+
+/// Semigroups with an identity element (`mempty` in Haskell).
+pub trait Monoid: Semigroup {
+    fn empty() -> Self;
+
+    /// Combines all the elements from left to right.
+    #[inline(always)]
+    fn concat<I>(iter: I) -> Self
+    where
+        Self: Sized,
+        I: IntoIterator<Item = Self>,
+    {
+        iter.into_iter().fold(Self::empty(), Self::combine)
+    }
+}
+
+impl Semigroup for String {
+    #[inline(always)]
+    fn combine(mut self, other: Self) -> Self {
+        self.push_str(&other);
+        self
+    }
+}
+
+impl Monoid for String {
+    #[inline(always)]
+    fn empty() -> Self {
+        String::new()
+    }
+}
+
+impl<T> Semigroup for Vec<T> {
+    #[inline(always)]
+    fn combine(mut self, mut other: Self) -> Self {
+        self.append(&mut other);
+        self
+    }
+}
+
+impl<T> Monoid for Vec<T> {
+    #[inline(always)]
+    fn empty() -> Self {
+        Vec::new()
+    }
+}
+
+/// Combines the contents if both are present; `None` is the identity.
+impl<S: Semigroup> Semigroup for Option<S> {
+    #[inline(always)]
+    fn combine(self, other: Self) -> Self {
+        match (self, other) {
+            (Some(a), Some(b)) => Some(a.combine(b)),
+            (a, None) => a,
+            (None, b) => b,
+        }
+    }
+}
+
+impl<S: Semigroup> Monoid for Option<S> {
+    #[inline(always)]
+    fn empty() -> Self {
+        None
+    }
+}
+
+/// The monoid of numbers under addition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Sum<T>(pub T);
+
+impl<T: Add<Output = T>> Semigroup for Sum<T> {
+    #[inline(always)]
+    fn combine(self, other: Self) -> Self {
+        Sum(self.0 + other.0)
+    }
+}
+
+/// The monoid of numbers under multiplication.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Product<T>(pub T);
+
+impl<T: Mul<Output = T>> Semigroup for Product<T> {
+    #[inline(always)]
+    fn combine(self, other: Self) -> Self {
+        Product(self.0 * other.0)
+    }
+}
+
+macro_rules! numeric_monoids {
+    ($zero:literal, $one:literal: $($t:ty),*) => {
+        $(
+            impl Monoid for Sum<$t> {
+                #[inline(always)]
+                fn empty() -> Self {
+                    Sum($zero)
+                }
+            }
+
+            impl Monoid for Product<$t> {
+                #[inline(always)]
+                fn empty() -> Self {
+                    Product($one)
+                }
+            }
+        )*
+    };
+}
+
+numeric_monoids!(0, 1: i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+numeric_monoids!(0.0, 1.0: f32, f64);
+
+impl Semigroup for () {
+    #[inline(always)]
+    fn combine(self, _: Self) -> Self {}
+}
+
+impl Monoid for () {
+    #[inline(always)]
+    fn empty() -> Self {}
+}
+
+macro_rules! tuple_monoids {
+    ($(($($t:ident $a:ident $b:ident),*)),*) => {
+        $(
+            impl<$($t: Semigroup),*> Semigroup for ($($t,)*) {
+                #[inline(always)]
+                fn combine(self, other: Self) -> Self {
+                    let ($($a,)*) = self;
+                    let ($($b,)*) = other;
+                    ($($a.combine($b),)*)
+                }
+            }
+
+            impl<$($t: Monoid),*> Monoid for ($($t,)*) {
+                #[inline(always)]
+                fn empty() -> Self {
+                    ($($t::empty(),)*)
+                }
+            }
+        )*
+    };
+}
+
+tuple_monoids!(
+    (S a0 b0),
+    (S a0 b0, T a1 b1),
+    (S a0 b0, T a1 b1, U a2 b2),
+    (S a0 b0, T a1 b1, U a2 b2, V a3 b3)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monoid_concat() {
+        assert_eq!(String::concat(["a".into(), "b".into(), "c".into()]), "abc");
+        assert_eq!(Sum::concat([1, 2, 3].map(Sum)), Sum(6));
+        assert_eq!(Product::concat([2.0, 3.0].map(Product)), Product(6.0));
+        assert_eq!(
+            Option::concat([None, Some(vec![1]), None, Some(vec![2])]),
+            Some(vec![1, 2])
+        );
+        assert_eq!(
+            <(Sum<u8>, String)>::concat([(Sum(1), "x".into()), (Sum(2), "y".into())]),
+            (Sum(3), "xy".into())
+        );
+    }
+}
@@ -1,13 +1,16 @@
 pub use super::data::{Functor, Pointed};
 use crate::data::unsafe_collect_array;
+pub use crate::fail::FailInfo;
 pub use crate::impls::*;
+use crate::monoid::{Monoid, Semigroup};
+use std::cmp::Ordering;
 
 pub struct AsNonlinear<F>(std::marker::PhantomData<F>);
 
 impl<F: Functor> AsNonlinear<F> {
-    pub fn fmap<A, B, G>(f: G, fa: F::Container<A>) -> F::Container<B>
+    pub fn fmap<'a, A: 'a, B: 'a, G>(f: G, fa: F::Container<'a, A>) -> F::Container<'a, B>
     where
-        G: FnMut(A) -> B,
+        G: FnMut(A) -> B + 'a,
     {
         <F as Functor>::fmap(f, fa)
     }
@@ -15,40 +18,86 @@ impl<F: Functor> AsNonlinear<F> {
 
 impl<F: Pointed> AsNonlinear<F> {
     #[inline(always)]
-    pub fn pure<T: Clone>(t: T) -> F::Container<T> {
+    pub fn pure<'a, T: Clone + 'a>(t: T) -> F::Container<'a, T> {
         <F as Pointed>::pure(t)
     }
 }
 
 pub trait Apply: Functor {
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A, B, C: 'a, F>(
         f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        A: Clone,
-        B: Clone,
-        F: FnMut(A, B) -> C;
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A, B) -> C + 'a;
+
+    /// Sequences two effects, keeping the result of the second (`*>` in Haskell).
+    #[inline(always)]
+    fn then<'a, A, B>(
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, B>
+    where
+        A: Clone + 'a,
+        B: Clone + 'a,
+    {
+        Self::zip_with(|_, b| b, fa, fb)
+    }
+
+    /// Sequences two effects, keeping the result of the first (`<*` in Haskell).
+    #[inline(always)]
+    fn then_left<'a, A, B>(
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, A>
+    where
+        A: Clone + 'a,
+        B: Clone + 'a,
+    {
+        Self::zip_with(|a, _| a, fa, fb)
+    }
 }
 
 impl<F: Apply> AsNonlinear<F> {
     #[inline(always)]
-    pub fn zip_with<A, B, C, G>(f: G, fa: F::Container<A>, fb: F::Container<B>) -> F::Container<C>
+    pub fn zip_with<'a, A, B, C: 'a, G>(
+        f: G,
+        fa: F::Container<'a, A>,
+        fb: F::Container<'a, B>,
+    ) -> F::Container<'a, C>
     where
-        A: Clone,
-        B: Clone,
-        G: FnMut(A, B) -> C,
+        A: Clone + 'a,
+        B: Clone + 'a,
+        G: FnMut(A, B) -> C + 'a,
     {
         <F as Apply>::zip_with(f, fa, fb)
     }
+
+    #[inline(always)]
+    pub fn then<'a, A: Clone + 'a, B: Clone + 'a>(
+        fa: F::Container<'a, A>,
+        fb: F::Container<'a, B>,
+    ) -> F::Container<'a, B> {
+        <F as Apply>::then(fa, fb)
+    }
+
+    #[inline(always)]
+    pub fn then_left<'a, A: Clone + 'a, B: Clone + 'a>(
+        fa: F::Container<'a, A>,
+        fb: F::Container<'a, B>,
+    ) -> F::Container<'a, A> {
+        <F as Apply>::then_left(fa, fb)
+    }
 }
 
 impl Apply for Identity {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(mut f: F, a: A, b: B) -> C
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(mut f: F, a: A, b: B) -> C
     where
-        F: FnMut(A, B) -> C,
+        F: FnMut(A, B) -> C + 'a,
     {
         f(a, b)
     }
@@ -56,13 +105,13 @@ impl Apply for Identity {
 
 impl Apply for OptionFunctor {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
         mut f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        F: FnMut(A, B) -> C,
+        F: FnMut(A, B) -> C + 'a,
     {
         fa.zip(fb).map(|(a, b)| f(a, b))
     }
@@ -70,13 +119,13 @@ impl Apply for OptionFunctor {
 
 impl<E> Apply for ResultFunctor<E> {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
         mut f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        F: FnMut(A, B) -> C,
+        F: FnMut(A, B) -> C + 'a,
     {
         fa.and_then(|a| fb.map(|b| f(a, b)))
     }
@@ -84,13 +133,13 @@ impl<E> Apply for ResultFunctor<E> {
 
 impl Apply for ZipVec {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A: 'a, B: 'a, C: 'a, F>(
         mut f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        F: FnMut(A, B) -> C,
+        F: FnMut(A, B) -> C + 'a,
     {
         fa.into_iter().zip(fb).map(|(a, b)| f(a, b)).collect()
     }
@@ -98,15 +147,15 @@ impl Apply for ZipVec {
 
 impl Apply for UndetVec {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A, B, C: 'a, F>(
         mut f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        A: Clone,
-        B: Clone,
-        F: FnMut(A, B) -> C,
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A, B) -> C + 'a,
     {
         fa.into_iter()
             .flat_map(|a| fb.iter().map(move |b| (a.clone(), b.clone())))
@@ -117,15 +166,15 @@ impl Apply for UndetVec {
 
 impl Apply for V2 {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A, B, C: 'a, F>(
         mut f: F,
-        (a, b): Self::Container<A>,
-        (c, d): Self::Container<B>,
-    ) -> Self::Container<C>
+        (a, b): Self::Container<'a, A>,
+        (c, d): Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        A: Clone,
-        B: Clone,
-        F: FnMut(A, B) -> C,
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A, B) -> C + 'a,
     {
         (f(a, c), f(b, d))
     }
@@ -133,26 +182,102 @@ impl Apply for V2 {
 
 impl<const N: usize> Apply for ArrayFunctor<N> {
     #[inline(always)]
-    fn zip_with<A, B, C, F>(
+    fn zip_with<'a, A, B, C: 'a, F>(
         f: F,
-        fa: Self::Container<A>,
-        fb: Self::Container<B>,
-    ) -> Self::Container<C>
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
     where
-        A: Clone,
-        B: Clone,
-        F: FnMut(A, B) -> C,
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A, B) -> C + 'a,
     {
         <Self as crate::data::Apply>::zip_with(f, fa, fb)
     }
 }
 
+/// Combines the errors of both sides if both fail.
+impl<E: Semigroup> Apply for Validation<E> {
+    #[inline(always)]
+    fn zip_with<'a, A, B, C: 'a, F>(
+        mut f: F,
+        fa: Self::Container<'a, A>,
+        fb: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A, B) -> C + 'a,
+    {
+        match (fa, fb) {
+            (Ok(a), Ok(b)) => Ok(f(a, b)),
+            (Err(e1), Err(e2)) => Err(e1.combine(e2)),
+            (Err(e), Ok(_)) | (Ok(_), Err(e)) => Err(e),
+        }
+    }
+}
+
+impl<W: Semigroup> Apply for Writer<W> {
+    #[inline(always)]
+    fn zip_with<'a, A, B, C: 'a, F>(
+        mut f: F,
+        (a, w1): Self::Container<'a, A>,
+        (b, w2): Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A, B) -> C + 'a,
+    {
+        (f(a, b), w1.combine(w2))
+    }
+}
+
+impl<R: 'static> Apply for Reader<R> {
+    #[inline(always)]
+    fn zip_with<'a, A, B, C: 'a, F>(
+        mut f: F,
+        m: Self::Container<'a, A>,
+        n: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A, B) -> C + 'a,
+    {
+        Box::new(move |r: &R| f(m(r), n(r)))
+    }
+}
+
+impl<S: 'static> Apply for State<S> {
+    #[inline(always)]
+    fn zip_with<'a, A, B, C: 'a, F>(
+        mut f: F,
+        m: Self::Container<'a, A>,
+        n: Self::Container<'a, B>,
+    ) -> Self::Container<'a, C>
+    where
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A, B) -> C + 'a,
+    {
+        Box::new(move |s: S| {
+            let (a, s) = m(s);
+            let (b, s) = n(s);
+            (f(a, b), s)
+        })
+    }
+}
+
 pub trait Alternative: Apply + Pointed {
-    fn empty<T>() -> Self::Container<T>;
-    fn choice<T>(a: Self::Container<T>, b: Self::Container<T>) -> Self::Container<T>;
+    fn empty<'a, T: 'a>() -> Self::Container<'a, T>;
+    fn choice<'a, T: 'a>(
+        a: Self::Container<'a, T>,
+        b: Self::Container<'a, T>,
+    ) -> Self::Container<'a, T>;
 
     #[inline(always)]
-    fn guard(p: bool) -> Self::Container<()> {
+    fn guard<'a>(p: bool) -> Self::Container<'a, ()> {
         if p {
             Self::pure(())
         } else {
@@ -163,88 +288,159 @@ pub trait Alternative: Apply + Pointed {
 
 impl<F: Alternative> AsNonlinear<F> {
     #[inline(always)]
-    pub fn empty<T>() -> F::Container<T> {
+    pub fn empty<'a, T: 'a>() -> F::Container<'a, T> {
         <F as Alternative>::empty()
     }
 
     #[inline(always)]
-    pub fn choice<T>(a: F::Container<T>, b: F::Container<T>) -> F::Container<T> {
+    pub fn choice<'a, T: 'a>(
+        a: F::Container<'a, T>,
+        b: F::Container<'a, T>,
+    ) -> F::Container<'a, T> {
         <F as Alternative>::choice(a, b)
     }
 
     #[inline(always)]
-    pub fn guard(p: bool) -> F::Container<()> {
+    pub fn guard<'a>(p: bool) -> F::Container<'a, ()> {
         <F as Alternative>::guard(p)
     }
 }
 
 impl Alternative for OptionFunctor {
     #[inline(always)]
-    fn empty<T>() -> Self::Container<T> {
+    fn empty<'a, T: 'a>() -> Self::Container<'a, T> {
         None
     }
 
     #[inline(always)]
-    fn choice<T>(a: Self::Container<T>, b: Self::Container<T>) -> Self::Container<T> {
+    fn choice<'a, T: 'a>(
+        a: Self::Container<'a, T>,
+        b: Self::Container<'a, T>,
+    ) -> Self::Container<'a, T> {
         a.or(b)
     }
 }
 
 impl Alternative for UndetVec {
     #[inline(always)]
-    fn empty<T>() -> Self::Container<T> {
+    fn empty<'a, T: 'a>() -> Self::Container<'a, T> {
         vec![]
     }
 
     #[inline(always)]
-    fn choice<T>(mut a: Self::Container<T>, b: Self::Container<T>) -> Self::Container<T> {
+    fn choice<'a, T: 'a>(
+        mut a: Self::Container<'a, T>,
+        b: Self::Container<'a, T>,
+    ) -> Self::Container<'a, T> {
         a.extend(b);
         a
     }
 }
 
 pub trait Monad: Apply + Pointed {
-    fn and_then<A, B, F>(fa: Self::Container<A>, f: F) -> Self::Container<B>
+    fn and_then<'a, A, B, F>(fa: Self::Container<'a, A>, f: F) -> Self::Container<'a, B>
     where
-        A: Clone,
-        B: Clone,
-        F: FnMut(A) -> Self::Container<B>;
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A) -> Self::Container<'a, B> + 'a;
 
     #[inline(always)]
-    fn flatten<A>(ffa: Self::Container<Self::Container<A>>) -> Self::Container<A>
+    fn flatten<'a, A>(ffa: Self::Container<'a, Self::Container<'a, A>>) -> Self::Container<'a, A>
     where
-        A: Clone,
-        Self::Container<A>: Clone,
+        A: Clone + 'a,
+        Self::Container<'a, A>: Clone,
     {
         <Self as Monad>::and_then(ffa, |fa| fa)
     }
+
+    /// Runs the effect for each element in order, discarding the results.
+    ///
+    /// By default, `f` is applied to every element up front, and the effects are sequenced with `then`;
+    /// short-circuiting monads override it to stop at the first failure.
+    #[inline(always)]
+    fn for_each<'a, I, F>(iter: I, mut f: F) -> Self::Container<'a, ()>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item) -> Self::Container<'a, ()>,
+    {
+        iter.into_iter()
+            .fold(<Self as Pointed>::pure(()), |acc, a| {
+                <Self as Apply>::then(acc, f(a))
+            })
+    }
+
+    /// Runs the effect for each element in order, collecting the results.
+    ///
+    /// As with [`Monad::for_each`], the effects are built up front and joined with `zip_with` by default.
+    #[inline(always)]
+    fn traverse_iter<'a, I, B, F>(iter: I, mut f: F) -> Self::Container<'a, Vec<B>>
+    where
+        I: IntoIterator,
+        B: Clone + 'a,
+        F: FnMut(I::Item) -> Self::Container<'a, B>,
+    {
+        iter.into_iter()
+            .fold(<Self as Pointed>::pure(Vec::new()), |acc, a| {
+                <Self as Apply>::zip_with(
+                    |mut bs: Vec<B>, b| {
+                        bs.push(b);
+                        bs
+                    },
+                    acc,
+                    f(a),
+                )
+            })
+    }
 }
 
 impl<F: Monad> AsNonlinear<F> {
     #[inline(always)]
-    pub fn and_then<A: Clone, B: Clone, G>(fa: F::Container<A>, f: G) -> F::Container<B>
+    pub fn and_then<'a, A: Clone + 'a, B: Clone + 'a, G>(
+        fa: F::Container<'a, A>,
+        f: G,
+    ) -> F::Container<'a, B>
     where
-        G: FnMut(A) -> F::Container<B>,
+        G: FnMut(A) -> F::Container<'a, B> + 'a,
     {
         <F as Monad>::and_then(fa, f)
     }
 
     #[inline(always)]
-    pub fn flatten<A: Clone>(ffa: F::Container<F::Container<A>>) -> F::Container<A>
+    pub fn flatten<'a, A: Clone + 'a>(
+        ffa: F::Container<'a, F::Container<'a, A>>,
+    ) -> F::Container<'a, A>
     where
-        F::Container<A>: Clone,
+        F::Container<'a, A>: Clone,
     {
         <F as Monad>::flatten(ffa)
     }
+
+    #[inline(always)]
+    pub fn for_each<'a, I, G>(iter: I, f: G) -> F::Container<'a, ()>
+    where
+        I: IntoIterator,
+        G: FnMut(I::Item) -> F::Container<'a, ()>,
+    {
+        <F as Monad>::for_each(iter, f)
+    }
+
+    #[inline(always)]
+    pub fn traverse_iter<'a, I, B: Clone + 'a, G>(iter: I, f: G) -> F::Container<'a, Vec<B>>
+    where
+        I: IntoIterator,
+        G: FnMut(I::Item) -> F::Container<'a, B>,
+    {
+        <F as Monad>::traverse_iter(iter, f)
+    }
 }
 
 impl Monad for Identity {
     #[inline(always)]
-    fn and_then<A, B, F>(fa: Self::Container<A>, f: F) -> Self::Container<B>
+    fn and_then<'a, A, B, F>(fa: Self::Container<'a, A>, f: F) -> Self::Container<'a, B>
     where
-        A: Clone,
-        B: Clone,
-        F: FnOnce(A) -> Self::Container<B>,
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnOnce(A) -> Self::Container<'a, B> + 'a,
     {
         f(fa)
     }
@@ -252,11 +448,11 @@ impl Monad for Identity {
 
 impl Monad for OptionFunctor {
     #[inline(always)]
-    fn and_then<A, B, F>(fa: Self::Container<A>, f: F) -> Self::Container<B>
+    fn and_then<'a, A, B, F>(fa: Self::Container<'a, A>, f: F) -> Self::Container<'a, B>
     where
-        A: Clone,
-        B: Clone,
-        F: FnOnce(A) -> Self::Container<B>,
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnOnce(A) -> Self::Container<'a, B> + 'a,
     {
         fa.and_then(f)
     }
@@ -264,11 +460,11 @@ impl Monad for OptionFunctor {
 
 impl<E> Monad for ResultFunctor<E> {
     #[inline(always)]
-    fn and_then<A, B, F>(fa: Self::Container<A>, f: F) -> Self::Container<B>
+    fn and_then<'a, A, B, F>(fa: Self::Container<'a, A>, f: F) -> Self::Container<'a, B>
     where
-        A: Clone,
-        B: Clone,
-        F: FnOnce(A) -> Self::Container<B>,
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnOnce(A) -> Self::Container<'a, B> + 'a,
     {
         fa.and_then(f)
     }
@@ -276,11 +472,11 @@ impl<E> Monad for ResultFunctor<E> {
 
 impl Monad for UndetVec {
     #[inline(always)]
-    fn and_then<A, B, F>(fa: Self::Container<A>, f: F) -> Self::Container<B>
+    fn and_then<'a, A, B, F>(fa: Self::Container<'a, A>, f: F) -> Self::Container<'a, B>
     where
-        A: Clone,
-        B: Clone,
-        F: FnMut(A) -> Self::Container<B>,
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A) -> Self::Container<'a, B> + 'a,
     {
         fa.into_iter().flat_map(f).collect()
     }
@@ -289,11 +485,11 @@ impl Monad for UndetVec {
 /// Takes diagonal
 impl Monad for V2 {
     #[inline(always)]
-    fn and_then<A, B, F>((a, b): Self::Container<A>, mut f: F) -> Self::Container<B>
+    fn and_then<'a, A, B, F>((a, b): Self::Container<'a, A>, mut f: F) -> Self::Container<'a, B>
     where
-        A: Clone,
-        B: Clone,
-        F: FnMut(A) -> Self::Container<B>,
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A) -> Self::Container<'a, B> + 'a,
     {
         let (a, _) = f(a);
         let (_, b) = f(b);
@@ -304,11 +500,11 @@ impl Monad for V2 {
 /// Takes diagonal upon joining
 impl<const N: usize> Monad for ArrayFunctor<N> {
     #[inline(always)]
-    fn and_then<A, B, F>(xs: Self::Container<A>, mut f: F) -> Self::Container<B>
+    fn and_then<'a, A, B, F>(xs: Self::Container<'a, A>, mut f: F) -> Self::Container<'a, B>
     where
-        A: Clone,
-        B: Clone,
-        F: FnMut(A) -> Self::Container<B>,
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A) -> Self::Container<'a, B> + 'a,
     {
         unsafe_collect_array(
             xs.into_iter()
@@ -318,38 +514,165 @@ impl<const N: usize> Monad for ArrayFunctor<N> {
     }
 }
 
+impl<W: Monoid> Monad for Writer<W> {
+    #[inline(always)]
+    fn and_then<'a, A, B, F>((a, w1): Self::Container<'a, A>, mut f: F) -> Self::Container<'a, B>
+    where
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A) -> Self::Container<'a, B> + 'a,
+    {
+        let (b, w2) = f(a);
+        (b, w1.combine(w2))
+    }
+}
+
+impl<R: 'static> Monad for Reader<R> {
+    #[inline(always)]
+    fn and_then<'a, A, B, F>(m: Self::Container<'a, A>, mut f: F) -> Self::Container<'a, B>
+    where
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A) -> Self::Container<'a, B> + 'a,
+    {
+        Box::new(move |r: &R| f(m(r))(r))
+    }
+}
+
+impl<S: 'static> Monad for State<S> {
+    #[inline(always)]
+    fn and_then<'a, A, B, F>(m: Self::Container<'a, A>, mut f: F) -> Self::Container<'a, B>
+    where
+        A: Clone + 'a,
+        B: Clone + 'a,
+        F: FnMut(A) -> Self::Container<'a, B> + 'a,
+    {
+        Box::new(move |s: S| {
+            let (a, s) = m(s);
+            f(a)(s)
+        })
+    }
+}
+
 pub trait MonadFail: Monad {
-    fn fail<A>(msg: &str) -> Self::Container<A>;
+    fn fail<'a, A: 'a>(msg: &str) -> Self::Container<'a, A>;
+
+    /// Fails with structured information about the failed binding.
+    /// `qdo` calls it when a refutable pattern doesn't match; by default, it fails with the rendered message.
+    #[inline(always)]
+    fn fail_with<'a, A: 'a>(info: FailInfo) -> Self::Container<'a, A> {
+        Self::fail(&info.to_string())
+    }
 }
 
 impl<T: MonadFail> AsNonlinear<T> {
     #[inline(always)]
-    pub fn fail<A>(msg: &str) -> T::Container<A> {
+    pub fn fail<'a, A: 'a>(msg: &str) -> T::Container<'a, A> {
         <T as MonadFail>::fail(msg)
     }
+
+    #[inline(always)]
+    pub fn fail_with<'a, A: 'a>(info: FailInfo) -> T::Container<'a, A> {
+        <T as MonadFail>::fail_with(info)
+    }
 }
 
 impl MonadFail for OptionFunctor {
     #[inline(always)]
-    fn fail<A>(_msg: &str) -> Option<A> {
+    fn fail<'a, A: 'a>(_msg: &str) -> Option<A> {
         None
     }
 }
 
 impl<E: From<String>> MonadFail for ResultFunctor<E> {
     #[inline(always)]
-    fn fail<A>(msg: &str) -> Result<A, E> {
+    fn fail<'a, A: 'a>(msg: &str) -> Result<A, E> {
         Err(msg.to_string().into())
     }
 }
 
 impl MonadFail for UndetVec {
     #[inline(always)]
-    fn fail<A>(_msg: &str) -> Vec<A> {
+    fn fail<'a, A: 'a>(_msg: &str) -> Vec<A> {
         vec![]
     }
 }
 
+/// Hooks for `then` statements in `qdo` blocks, transforming the results of all the preceding statements
+/// as a whole, like `ORDER BY`, `GROUP BY` and `LIMIT` in SQL.
+pub trait Transform: Monad {
+    /// Takes the first `n` results.
+    fn take<'a, A: 'a>(n: usize, fa: Self::Container<'a, A>) -> Self::Container<'a, A>;
+
+    /// Sorts the results stably.
+    fn sort_by<'a, A: 'a, F>(compare: F, fa: Self::Container<'a, A>) -> Self::Container<'a, A>
+    where
+        F: FnMut(&A, &A) -> Ordering + 'a;
+
+    /// Sorts the results stably and groups the equal ones.
+    fn group_by<'a, A: 'a, F>(
+        compare: F,
+        fa: Self::Container<'a, A>,
+    ) -> Self::Container<'a, Vec<A>>
+    where
+        F: FnMut(&A, &A) -> Ordering + 'a;
+}
+
+impl<T: Transform> AsNonlinear<T> {
+    #[inline(always)]
+    pub fn take<'a, A: 'a>(n: usize, fa: T::Container<'a, A>) -> T::Container<'a, A> {
+        <T as Transform>::take(n, fa)
+    }
+
+    #[inline(always)]
+    pub fn sort_by<'a, A: 'a, F>(compare: F, fa: T::Container<'a, A>) -> T::Container<'a, A>
+    where
+        F: FnMut(&A, &A) -> Ordering + 'a,
+    {
+        <T as Transform>::sort_by(compare, fa)
+    }
+
+    #[inline(always)]
+    pub fn group_by<'a, A: 'a, F>(compare: F, fa: T::Container<'a, A>) -> T::Container<'a, Vec<A>>
+    where
+        F: FnMut(&A, &A) -> Ordering + 'a,
+    {
+        <T as Transform>::group_by(compare, fa)
+    }
+}
+
+impl Transform for UndetVec {
+    #[inline(always)]
+    fn take<'a, A: 'a>(n: usize, mut fa: Vec<A>) -> Vec<A> {
+        fa.truncate(n);
+        fa
+    }
+
+    #[inline(always)]
+    fn sort_by<'a, A: 'a, F>(compare: F, mut fa: Vec<A>) -> Vec<A>
+    where
+        F: FnMut(&A, &A) -> Ordering + 'a,
+    {
+        fa.sort_by(compare);
+        fa
+    }
+
+    fn group_by<'a, A: 'a, F>(mut compare: F, mut fa: Vec<A>) -> Vec<Vec<A>>
+    where
+        F: FnMut(&A, &A) -> Ordering + 'a,
+    {
+        fa.sort_by(&mut compare);
+        let mut groups: Vec<Vec<A>> = Vec::new();
+        for a in fa {
+            match groups.last_mut() {
+                Some(group) if compare(&group[0], &a) == Ordering::Equal => group.push(a),
+                _ => groups.push(vec![a]),
+            }
+        }
+        groups
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -372,4 +695,20 @@ mod tests {
             ArrayFunctor::and_then([1, 2, 3], |a| ArrayFunctor::fmap(|b| a + b, [4, 5, 6]))
         )
     }
+
+    #[test]
+    fn test_undet_traverse_iter() {
+        assert_eq!(
+            UndetVec::traverse_iter([1, 2], |x| vec![x, x * 10]),
+            vec![vec![1, 2], vec![1, 20], vec![10, 2], vec![10, 20]]
+        )
+    }
+
+    #[test]
+    fn test_undet_group_by() {
+        assert_eq!(
+            UndetVec::group_by(|a: &i32, b| (a % 3).cmp(&(b % 3)), vec![1, 2, 3, 4, 5, 6]),
+            vec![vec![3, 6], vec![1, 4], vec![2, 5]]
+        )
+    }
 }
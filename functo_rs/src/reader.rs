@@ -0,0 +1,68 @@
+//! The reader monad, threading a read-only environment through computations.
+//!
+//! [`Reader<R>`] is a functor in every hierarchy, whose container is a boxed function of the environment.
+//! It captures the continuations passed to `fmap` or `and_then`, hence lives as long as them.
+
+use crate::impls::Reader;
+
+/// A computation reading an environment of type `R`, as built by [`Reader`].
+pub type ReaderFn<'a, R, T> = Box<dyn FnOnce(&R) -> T + 'a>;
+
+impl<R> Reader<R> {
+    /// Runs the computation in the given environment.
+    #[inline(always)]
+    pub fn run<A>(m: ReaderFn<'_, R, A>, env: &R) -> A {
+        m(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::*;
+
+    #[derive(Clone)]
+    struct Config {
+        verbose: bool,
+        depth: usize,
+    }
+
+    #[test]
+    fn test_reader_ask_local() {
+        let m = Reader::and_then(Reader::asks(|c: &Config| c.depth), |d| {
+            Reader::fmap(
+                move |inner: Config| (d, inner.depth, inner.verbose),
+                Reader::local(
+                    |c: &Config| Config {
+                        depth: c.depth + 1,
+                        ..c.clone()
+                    },
+                    Reader::ask(),
+                ),
+            )
+        });
+        let config = Config {
+            verbose: true,
+            depth: 0,
+        };
+        assert_eq!(Reader::run(m, &config), (0, 1, true));
+    }
+
+    #[test]
+    fn test_reader_traverse_iter() {
+        let m = Reader::traverse_iter(1..=3, |i| Reader::asks(move |n: &usize| i * n));
+        assert_eq!(Reader::run(m, &10), vec![10, 20, 30]);
+    }
+
+    #[test]
+    fn test_reader_borrowed_capture() {
+        // Computations may borrow from the enclosing scope, for as long as they live.
+        let offsets = [1, 2];
+        let m = AsControl::<Reader<usize>>::zip_with(
+            |a: usize, b: usize| a + b,
+            Reader::asks(|n: &usize| n + offsets[0]),
+            Reader::fmap(|n: usize| n * offsets[1], Reader::ask()),
+        );
+        assert_eq!(Reader::run(m, &10), 31);
+    }
+}
@@ -0,0 +1,57 @@
+//! The state monad, threading a value of type `S` through computations.
+//!
+//! [`State<S>`] is a functor in every hierarchy, whose container is a boxed function of the state.
+//! Like [`Reader`](crate::impls::Reader), it captures the continuations, hence lives as long as them.
+
+use crate::impls::State;
+
+/// A computation transforming a state of type `S`, as built by [`State`].
+pub type StateFn<'a, S, T> = Box<dyn FnOnce(S) -> (T, S) + 'a>;
+
+impl<S> State<S> {
+    /// Runs the computation from the initial state, returning the result and the final state.
+    #[inline(always)]
+    pub fn run_state<A>(m: StateFn<'_, S, A>, init: S) -> (A, S) {
+        m(init)
+    }
+
+    /// Runs the computation from the initial state, returning only the result.
+    #[inline(always)]
+    pub fn eval_state<A>(m: StateFn<'_, S, A>, init: S) -> A {
+        m(init).0
+    }
+
+    /// Runs the computation from the initial state, returning only the final state.
+    #[inline(always)]
+    pub fn exec_state<A>(m: StateFn<'_, S, A>, init: S) -> S {
+        m(init).1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::*;
+
+    #[test]
+    fn test_state_counter() {
+        let tick = || State::<u32>::and_then(State::get(), |n| State::put(n + 1));
+        let m = State::and_then(tick(), move |()| {
+            State::then(tick(), State::gets(|n: &u32| n * 10))
+        });
+        assert_eq!(State::run_state(m, 1), (30, 3));
+    }
+
+    #[test]
+    fn test_state_traverse_iter() {
+        let m = State::traverse_iter(["a", "b", "c"], |s| {
+            State::and_then(State::get(), move |n: usize| {
+                State::then(State::modify(|n| n + 1), State::pure(format!("{s}{n}")))
+            })
+        });
+        assert_eq!(
+            State::run_state(m, 0),
+            (vec!["a0".into(), "b1".into(), "c2".into()], 3)
+        );
+    }
+}
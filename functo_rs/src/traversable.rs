@@ -0,0 +1,351 @@
+//! Folding and traversing the elements of [data functors][`crate::data`].
+//!
+//! [`Traversable::traverse`] is parameterised by an applicative in the `data` hierarchy.
+//! As every control applicative is also a data one, it works with both, e.g. turning
+//! `[Option<T>; N]` into `Option<[T; N]>` with `ArrayFunctor::<N>::sequence::<OptionFunctor, _>`.
+
+use crate::data::{unsafe_collect_array, Apply, Functor, Pointed};
+pub use crate::impls::*;
+use crate::monoid::Monoid;
+
+pub trait Foldable: Functor {
+    /// Folds the elements from right to left (`foldr` in Haskell).
+    fn fold_r<'a, A: 'a, B, F>(f: F, init: B, fa: Self::Container<'a, A>) -> B
+    where
+        F: FnMut(A, B) -> B;
+
+    /// Maps the elements into a monoid and combines them from left to right.
+    #[inline(always)]
+    fn fold_map<'a, A: 'a, M, F>(mut f: F, fa: Self::Container<'a, A>) -> M
+    where
+        M: Monoid,
+        F: FnMut(A) -> M,
+    {
+        Self::fold_r(|a, m| f(a).combine(m), M::empty(), fa)
+    }
+
+    /// The number of elements.
+    #[inline(always)]
+    fn length<'a, A: 'a>(fa: Self::Container<'a, A>) -> usize {
+        Self::fold_r(|_, n| n + 1, 0, fa)
+    }
+
+    /// Collects the elements from left to right.
+    #[inline(always)]
+    fn to_vec<'a, A: 'a>(fa: Self::Container<'a, A>) -> Vec<A> {
+        let mut v = Self::fold_r(
+            |a, mut v: Vec<A>| {
+                v.push(a);
+                v
+            },
+            Vec::new(),
+            fa,
+        );
+        v.reverse();
+        v
+    }
+}
+
+impl Foldable for Identity {
+    #[inline(always)]
+    fn fold_r<'a, A: 'a, B, F>(mut f: F, init: B, a: A) -> B
+    where
+        F: FnMut(A, B) -> B,
+    {
+        f(a, init)
+    }
+}
+
+impl Foldable for OptionFunctor {
+    #[inline(always)]
+    fn fold_r<'a, A: 'a, B, F>(mut f: F, init: B, fa: Option<A>) -> B
+    where
+        F: FnMut(A, B) -> B,
+    {
+        match fa {
+            Some(a) => f(a, init),
+            None => init,
+        }
+    }
+}
+
+impl<E> Foldable for ResultFunctor<E> {
+    #[inline(always)]
+    fn fold_r<'a, A: 'a, B, F>(mut f: F, init: B, fa: Result<A, E>) -> B
+    where
+        F: FnMut(A, B) -> B,
+    {
+        match fa {
+            Ok(a) => f(a, init),
+            Err(_) => init,
+        }
+    }
+}
+
+impl Foldable for UndetVec {
+    #[inline(always)]
+    fn fold_r<'a, A: 'a, B, F>(mut f: F, init: B, fa: Vec<A>) -> B
+    where
+        F: FnMut(A, B) -> B,
+    {
+        fa.into_iter().rev().fold(init, |b, a| f(a, b))
+    }
+
+    #[inline(always)]
+    fn length<'a, A: 'a>(fa: Vec<A>) -> usize {
+        fa.len()
+    }
+
+    #[inline(always)]
+    fn to_vec<'a, A: 'a>(fa: Vec<A>) -> Vec<A> {
+        fa
+    }
+}
+
+impl Foldable for ZipVec {
+    #[inline(always)]
+    fn fold_r<'a, A: 'a, B, F>(f: F, init: B, fa: Vec<A>) -> B
+    where
+        F: FnMut(A, B) -> B,
+    {
+        UndetVec::fold_r(f, init, fa)
+    }
+
+    #[inline(always)]
+    fn length<'a, A: 'a>(fa: Vec<A>) -> usize {
+        fa.len()
+    }
+
+    #[inline(always)]
+    fn to_vec<'a, A: 'a>(fa: Vec<A>) -> Vec<A> {
+        fa
+    }
+}
+
+impl Foldable for V2 {
+    #[inline(always)]
+    fn fold_r<'a, A: 'a, B, F>(mut f: F, init: B, (a1, a2): (A, A)) -> B
+    where
+        F: FnMut(A, B) -> B,
+    {
+        let b = f(a2, init);
+        f(a1, b)
+    }
+}
+
+impl<const N: usize> Foldable for ArrayFunctor<N> {
+    #[inline(always)]
+    fn fold_r<'a, A: 'a, B, F>(mut f: F, init: B, fa: [A; N]) -> B
+    where
+        F: FnMut(A, B) -> B,
+    {
+        fa.into_iter().rev().fold(init, |b, a| f(a, b))
+    }
+
+    #[inline(always)]
+    fn length<'a, A: 'a>(_: [A; N]) -> usize {
+        N
+    }
+
+    #[inline(always)]
+    fn to_vec<'a, A: 'a>(fa: [A; N]) -> Vec<A> {
+        fa.into()
+    }
+}
+
+pub trait Traversable: Foldable {
+    /// Runs the effect for each element from left to right, collecting the results in the same shape.
+    fn traverse<'a, G, A: 'a, B: 'a, F>(
+        f: F,
+        ta: Self::Container<'a, A>,
+    ) -> G::Container<'a, Self::Container<'a, B>>
+    where
+        G: Apply + Pointed,
+        F: FnMut(A) -> G::Container<'a, B>;
+
+    /// Runs the effects of the elements from left to right.
+    #[inline(always)]
+    fn sequence<'a, G, A: 'a>(
+        tga: Self::Container<'a, G::Container<'a, A>>,
+    ) -> G::Container<'a, Self::Container<'a, A>>
+    where
+        G: Apply + Pointed,
+    {
+        Self::traverse::<G, _, _, _>(|ga| ga, tga)
+    }
+}
+
+/// Traverses the elements into a `Vec`, without requiring them to be `Clone` for `G::pure`.
+fn traverse_vec<'a, G, I, B: 'a, F>(mut f: F, iter: I) -> G::Container<'a, Vec<B>>
+where
+    G: Apply + Pointed,
+    I: IntoIterator,
+    F: FnMut(I::Item) -> G::Container<'a, B>,
+{
+    iter.into_iter()
+        .fold(G::fmap(|()| Vec::new(), G::pure(())), |acc, a| {
+            G::zip_with(
+                |mut bs: Vec<B>, b| {
+                    bs.push(b);
+                    bs
+                },
+                acc,
+                f(a),
+            )
+        })
+}
+
+impl Traversable for Identity {
+    #[inline(always)]
+    fn traverse<'a, G, A: 'a, B: 'a, F>(mut f: F, a: A) -> G::Container<'a, B>
+    where
+        G: Apply + Pointed,
+        F: FnMut(A) -> G::Container<'a, B>,
+    {
+        f(a)
+    }
+}
+
+impl Traversable for OptionFunctor {
+    #[inline(always)]
+    fn traverse<'a, G, A: 'a, B: 'a, F>(mut f: F, ta: Option<A>) -> G::Container<'a, Option<B>>
+    where
+        G: Apply + Pointed,
+        F: FnMut(A) -> G::Container<'a, B>,
+    {
+        match ta {
+            Some(a) => G::fmap(Some, f(a)),
+            None => G::fmap(|()| None, G::pure(())),
+        }
+    }
+}
+
+/// `Err` is kept as is, hence cloned if `G::pure` replicates it.
+impl<E: Clone> Traversable for ResultFunctor<E> {
+    #[inline(always)]
+    fn traverse<'a, G, A: 'a, B: 'a, F>(
+        mut f: F,
+        ta: Result<A, E>,
+    ) -> G::Container<'a, Result<B, E>>
+    where
+        G: Apply + Pointed,
+        F: FnMut(A) -> G::Container<'a, B>,
+    {
+        match ta {
+            Ok(a) => G::fmap(Ok, f(a)),
+            Err(e) => G::fmap(move |()| Err(e.clone()), G::pure(())),
+        }
+    }
+}
+
+impl Traversable for UndetVec {
+    #[inline(always)]
+    fn traverse<'a, G, A: 'a, B: 'a, F>(f: F, ta: Vec<A>) -> G::Container<'a, Vec<B>>
+    where
+        G: Apply + Pointed,
+        F: FnMut(A) -> G::Container<'a, B>,
+    {
+        traverse_vec::<G, _, _, _>(f, ta)
+    }
+}
+
+impl Traversable for ZipVec {
+    #[inline(always)]
+    fn traverse<'a, G, A: 'a, B: 'a, F>(f: F, ta: Vec<A>) -> G::Container<'a, Vec<B>>
+    where
+        G: Apply + Pointed,
+        F: FnMut(A) -> G::Container<'a, B>,
+    {
+        traverse_vec::<G, _, _, _>(f, ta)
+    }
+}
+
+impl Traversable for V2 {
+    #[inline(always)]
+    fn traverse<'a, G, A: 'a, B: 'a, F>(mut f: F, (a1, a2): (A, A)) -> G::Container<'a, (B, B)>
+    where
+        G: Apply + Pointed,
+        F: FnMut(A) -> G::Container<'a, B>,
+    {
+        let b1 = f(a1);
+        G::zip_with(|b1, b2| (b1, b2), b1, f(a2))
+    }
+}
+
+impl<const N: usize> Traversable for ArrayFunctor<N> {
+    #[inline(always)]
+    fn traverse<'a, G, A: 'a, B: 'a, F>(f: F, ta: [A; N]) -> G::Container<'a, [B; N]>
+    where
+        G: Apply + Pointed,
+        F: FnMut(A) -> G::Container<'a, B>,
+    {
+        G::fmap(
+            |bs: Vec<B>| unsafe_collect_array(bs.into_iter()),
+            traverse_vec::<G, _, _, _>(f, ta),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monoid::Sum;
+
+    #[test]
+    fn test_foldable() {
+        assert_eq!(ArrayFunctor::<3>::fold_map(Sum, [1, 2, 3]), Sum(6));
+        assert_eq!(
+            UndetVec::fold_r(
+                |a, s: String| format!("({a} {s})"),
+                "nil".into(),
+                vec![1, 2]
+            ),
+            "(1 (2 nil))"
+        );
+        assert_eq!(V2::to_vec(("a", "b")), vec!["a", "b"]);
+        assert_eq!(OptionFunctor::length(None::<u8>), 0);
+        assert_eq!(
+            ResultFunctor::<()>::fold_map(|s: &str| s.to_string(), Ok("ok")),
+            "ok"
+        );
+    }
+
+    #[test]
+    fn test_sequence_array_option() {
+        #[derive(Debug, PartialEq)]
+        struct NotClone(u8);
+        assert_eq!(
+            ArrayFunctor::<3>::sequence::<OptionFunctor, _>([
+                Some(NotClone(1)),
+                Some(NotClone(2)),
+                Some(NotClone(3))
+            ]),
+            Some([NotClone(1), NotClone(2), NotClone(3)])
+        );
+        assert_eq!(
+            ArrayFunctor::<2>::sequence::<OptionFunctor, _>([Some(1), None]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_traverse_applicatives() {
+        let parse = |s: &str| s.parse::<i32>().map_err(|e| e.to_string());
+        assert_eq!(
+            UndetVec::traverse::<ResultFunctor<String>, _, _, _>(parse, vec!["1", "2"]),
+            Ok(vec![1, 2])
+        );
+        assert!(
+            UndetVec::traverse::<ResultFunctor<String>, _, _, _>(parse, vec!["1", "x"]).is_err()
+        );
+        // Splits into two vectors with the pairwise applicative.
+        assert_eq!(
+            UndetVec::traverse::<V2, _, _, _>(|n| (n, -n), vec![1, 2]),
+            (vec![1, 2], vec![-1, -2])
+        );
+        assert_eq!(
+            OptionFunctor::traverse::<V2, _, _, _>(|n| (n, n), None::<u8>),
+            (None, None)
+        );
+    }
+}
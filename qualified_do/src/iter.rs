@@ -1,5 +1,8 @@
+use crate::FailInfo;
 use core::str;
+use functo_rs::nonlinear::{Transform, UndetVec};
 use itertools::Itertools;
+use std::cmp::Ordering;
 use std::iter::Iterator;
 use std::iter::*;
 pub enum ZipIter {}
@@ -29,6 +32,24 @@ impl ZipIter {
         Box::new(a.into_iter().zip(b).map(move |(a, b)| f(a, b)))
     }
 
+    #[inline(always)]
+    pub fn then<'a, A, B>(a: A, b: B) -> Box<dyn Iterator<Item = B::Item> + 'a>
+    where
+        A: IntoIterator + 'a,
+        B: IntoIterator + 'a,
+    {
+        Box::new(a.into_iter().zip(b).map(|(_, b)| b))
+    }
+
+    #[inline(always)]
+    pub fn then_left<'a, A, B>(a: A, b: B) -> Box<dyn Iterator<Item = A::Item> + 'a>
+    where
+        A: IntoIterator + 'a,
+        B: IntoIterator + 'a,
+    {
+        Box::new(a.into_iter().zip(b).map(|(a, _)| a))
+    }
+
     #[inline(always)]
     pub fn empty<'a, A: 'a>() -> Box<dyn Iterator<Item = A> + 'a> {
         Box::new(empty())
@@ -91,6 +112,30 @@ impl Iter {
         )
     }
 
+    #[inline(always)]
+    pub fn then<'a, A, B>(a: A, b: B) -> Box<dyn Iterator<Item = B::Item> + 'a>
+    where
+        A: IntoIterator,
+        B: IntoIterator,
+        B::IntoIter: Clone + 'a,
+        A::IntoIter: 'a,
+    {
+        let b = b.into_iter();
+        Box::new(a.into_iter().flat_map(move |_| b.clone()))
+    }
+
+    #[inline(always)]
+    pub fn then_left<'a, A, B>(a: A, b: B) -> Box<dyn Iterator<Item = A::Item> + 'a>
+    where
+        A: IntoIterator,
+        B: IntoIterator,
+        B::IntoIter: Clone + 'a,
+        A::Item: Clone + 'a,
+        A::IntoIter: 'a,
+    {
+        Self::zip_with(|a, _| a, a, b)
+    }
+
     #[inline(always)]
     pub fn and_then<'a, A, B, F>(a: A, f: F) -> Box<dyn Iterator<Item = B::Item> + 'a>
     where
@@ -102,11 +147,50 @@ impl Iter {
         Box::new(a.into_iter().flat_map(f))
     }
 
+    /// Collects every combination of the results of `f` for each element.
+    pub fn traverse_iter<'a, I, B, F>(
+        iter: I,
+        mut f: F,
+    ) -> Box<dyn Iterator<Item = Vec<B::Item>> + 'a>
+    where
+        I: IntoIterator,
+        B: IntoIterator,
+        B::Item: Clone + 'a,
+        F: FnMut(I::Item) -> B,
+    {
+        iter.into_iter().fold(Self::pure(Vec::new()), |acc, a| {
+            let bs = f(a).into_iter().collect::<Vec<_>>();
+            Box::new(acc.flat_map(move |v| {
+                bs.clone().into_iter().map(move |b| {
+                    let mut v = v.clone();
+                    v.push(b);
+                    v
+                })
+            }))
+        })
+    }
+
+    /// Runs `f` for each element, discarding the results.
+    pub fn for_each<'a, I, B, F>(iter: I, f: F) -> Box<dyn Iterator<Item = ()> + 'a>
+    where
+        I: IntoIterator,
+        B: IntoIterator,
+        B::Item: Clone + 'a,
+        F: FnMut(I::Item) -> B,
+    {
+        Self::fmap(|_| (), Self::traverse_iter(iter, f))
+    }
+
     #[inline(always)]
     pub fn fail<'a, T: 'a>(_: &str) -> Box<dyn Iterator<Item = T> + 'a> {
         Self::empty()
     }
 
+    #[inline(always)]
+    pub fn fail_with<'a, T: 'a>(_: FailInfo) -> Box<dyn Iterator<Item = T> + 'a> {
+        Self::empty()
+    }
+
     #[inline(always)]
     pub fn join<A, B>(a: A) -> Flatten<A::IntoIter>
     where
@@ -139,11 +223,40 @@ impl Iter {
     {
         Box::new(l.into_iter().chain(r))
     }
+
+    #[inline(always)]
+    pub fn take<'a, A>(n: usize, a: A) -> Box<dyn Iterator<Item = A::Item> + 'a>
+    where
+        A: IntoIterator,
+        A::IntoIter: 'a,
+    {
+        Box::new(a.into_iter().take(n))
+    }
+
+    /// Collects and sorts the items stably.
+    pub fn sort_by<'a, A, F>(compare: F, a: A) -> Box<dyn Iterator<Item = A::Item> + 'a>
+    where
+        A: IntoIterator,
+        A::Item: 'a,
+        F: FnMut(&A::Item, &A::Item) -> Ordering,
+    {
+        Box::new(UndetVec::sort_by(compare, a.into_iter().collect()).into_iter())
+    }
+
+    /// Collects and sorts the items stably, and groups the equal ones.
+    pub fn group_by<'a, A, F>(compare: F, a: A) -> Box<dyn Iterator<Item = Vec<A::Item>> + 'a>
+    where
+        A: IntoIterator,
+        A::Item: 'a,
+        F: FnMut(&A::Item, &A::Item) -> Ordering,
+    {
+        Box::new(UndetVec::group_by(compare, a.into_iter().collect()).into_iter())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::qdo;
+    use super::super::{qcomp, qdo};
     use super::*;
 
     #[test]
@@ -250,4 +363,128 @@ mod tests {
             .collect::<HashSet<_>>();
         assert_eq!(ans, expected);
     }
+
+    #[test]
+    fn test_zipiter_let_floating() {
+        let is: Vec<i64> = vec![1, 2, 3];
+        let js: Vec<i64> = vec![4, 5, 6];
+        let ans: Vec<i64> = {
+            let is = is.clone();
+            let js = js.clone();
+            qdo! {ZipIter {
+                i <- is;
+                let k = i + 100;
+                j <- js;
+                return j + k
+            }}
+            .collect()
+        };
+        assert_eq!(ans, vec![105, 107, 109]);
+    }
+
+    #[test]
+    fn test_iter_monadic_non_clone() {
+        #[derive(Debug, PartialEq, Eq)]
+        struct NoClone(i64);
+        let ans: Vec<(NoClone, i64)> = qdo! {#[monadic] Iter {
+            x <- vec![1, 2];
+            y <- vec![10, 20];
+            return (NoClone(x), y)
+        }}
+        .collect();
+        assert_eq!(
+            ans,
+            vec![
+                (NoClone(1), 10),
+                (NoClone(1), 20),
+                (NoClone(2), 10),
+                (NoClone(2), 20)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_iter_last_expr_non_clone() {
+        // The last expression is chained with `and_then`, which requires no `Clone` iterator.
+        struct CountDown(u8);
+        impl Iterator for CountDown {
+            type Item = u8;
+            fn next(&mut self) -> Option<u8> {
+                self.0 = self.0.checked_sub(1)?;
+                Some(self.0)
+            }
+        }
+        let answer = qdo! { Iter {
+            x <- 0..3u8;
+            guard x > 0;
+            CountDown(2)
+        }}
+        .collect::<Vec<_>>();
+        assert_eq!(answer, vec![1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_iter_for() {
+        let answer = qdo! {#[monadic] Iter {
+            x <- 1..=2;
+            ys <- for d in [0, 10] {
+                return x + d
+            };
+            bits <- for _ in 0..2 {
+                vec![false, true]
+            };
+            return (ys.clone(), bits)
+        }}
+        .collect::<Vec<_>>();
+        let bits = [
+            vec![false, false],
+            vec![false, true],
+            vec![true, false],
+            vec![true, true],
+        ];
+        let expected = [1, 2]
+            .into_iter()
+            .flat_map(|x| bits.iter().map(move |b| (vec![x, x + 10], b.clone())))
+            .collect::<Vec<_>>();
+        assert_eq!(answer, expected);
+    }
+
+    #[test]
+    fn test_zipiter_then() {
+        let answer = qdo! {#[applicative] ZipIter {
+            vec![(); 2];
+            "abc".chars()
+        }}
+        .collect::<Vec<_>>();
+        assert_eq!(answer, vec!['a', 'b']);
+    }
+
+    #[test]
+    fn test_zipiter_qcomp_parallel() {
+        let answer = qcomp![ZipIter; (y, c) | x <- 1.., let y = x * 2 | c <- "abc".chars()]
+            .collect::<Vec<_>>();
+        assert_eq!(answer, vec![(2, 'a'), (4, 'b'), (6, 'c')]);
+    }
+
+    #[test]
+    fn test_iter_then_query() {
+        let sales = [
+            ("apple", 3),
+            ("banana", 5),
+            ("apple", 4),
+            ("cherry", 1),
+            ("banana", 2),
+        ];
+        let answer = qdo! { Iter {
+            (item, amount) <- sales;
+            guard amount > 1;
+            then group by item;
+            let total = amount.iter().sum::<i32>();
+            then sort_by ::core::cmp::Reverse(total);
+            then take 2;
+            return (item[0], total)
+        }}
+        .collect::<Vec<_>>();
+        assert_eq!(answer, vec![("apple", 7), ("banana", 7)]);
+    }
 }
@@ -1,4 +1,4 @@
-pub use qualified_do_macro::qdo;
+pub use qualified_do_macro::{ado, qcomp, qdo, qdo_fn, qdo_stringify};
 
 pub mod iter;
 pub use iter::Iter;
@@ -6,11 +6,20 @@ pub use iter::ZipIter;
 
 pub use functo_rs::control::AsControl;
 pub use functo_rs::data::AsData;
+pub use functo_rs::fail::FailInfo;
 pub use functo_rs::impls::*;
+pub use functo_rs::monoid::{Monoid, Product, Semigroup, Sum};
 pub use functo_rs::nonlinear::AsNonlinear;
+pub use functo_rs::reader::ReaderFn;
+pub use functo_rs::state::StateFn;
 
 pub type Optioned = AsControl<OptionFunctor>;
 pub type Resulted<E> = AsControl<ResultFunctor<E>>;
+pub type Checked<E> = AsControl<CheckedResult<E>>;
+pub type Readered<R> = AsControl<Reader<R>>;
+pub type Stated<S> = AsControl<State<S>>;
+pub type Written<W> = AsControl<Writer<W>>;
+pub type Validated<E> = AsControl<Validation<E>>;
 
 #[cfg(test)]
 mod tests {
@@ -25,6 +34,57 @@ mod tests {
         assert_eq!(answer, Some(103));
     }
 
+    #[test]
+    fn test_optioned_discarded_effect() {
+        let answer = qdo! {Optioned {
+            x <- Some(1);
+            None::<()>;
+            y <- Some(2);
+            return x + y
+        }};
+        assert_eq!(answer, None);
+    }
+
+    #[test]
+    fn test_optioned_last_expr_lazy() {
+        // The last expression is evaluated only if the preceding statements succeed.
+        let v: &[i32] = &[];
+        let answer = qdo! {Optioned {
+            v.first();
+            Some(v[0])
+        }};
+        assert_eq!(answer, None);
+    }
+
+    #[test]
+    fn test_optioned_plain_if_match() {
+        // Parenthesised `if` and `match` are plain Rust expressions, whose arms are not do-blocks.
+        let ans = |c: bool, n: Option<u8>| {
+            let mut log = Vec::new();
+            let ans = qdo! {Optioned {
+                x <- (if c { log.push("then"); Some(1) } else { None });
+                y <- (match n { Some(n) => { log.push("some"); Some(n) } None => Some(0) });
+                return x + y
+            }};
+            (ans, log)
+        };
+        assert_eq!(ans(true, Some(2)), (Some(3), vec!["then", "some"]));
+        assert_eq!(ans(true, None), (Some(1), vec!["then"]));
+        assert_eq!(ans(false, Some(2)), (None, vec!["some"]));
+    }
+
+    #[test]
+    fn test_optioned_plain_for() {
+        // A plain Rust `for` loop runs in a `let`, where the body is not a do-block.
+        let ans = qdo! {Optioned {
+            x <- Some(3);
+            let mut total = x;
+            let () = for d in [10, 20] { total += d };
+            return total
+        }};
+        assert_eq!(ans, Some(33));
+    }
+
     #[test]
     fn text_optioned_resulted_nested() {
         #[derive(Debug, Copy, Clone)]
@@ -48,4 +108,283 @@ mod tests {
         assert_eq!(ans(Go::Go), Ok(1106));
         assert_eq!(ans(Go::NoGo), Err("Failed".to_string()));
     }
+
+    #[test]
+    fn test_direct_borrows_across_binds() {
+        let ans = |input: Option<String>| -> Result<usize, String> {
+            qdo! {#[direct] Resulted {
+                s <- input.ok_or("no input".to_string());
+                let r = &s;
+                n <- r.parse::<usize>().map_err(|e| e.to_string());
+                Some(m) <- Ok(r.len().checked_sub(n));
+                return m
+            }}
+        };
+        assert_eq!(ans(Some("1".to_string())), Ok(0));
+        assert_eq!(ans(None), Err("no input".to_string()));
+        let err = ans(Some("9".to_string())).unwrap_err();
+        assert!(err.starts_with(&format!("Pattern match failed at {}:", file!())));
+        assert!(err.ends_with("\n  expected: Some(m)\n  scrutinee: Ok(r.len().checked_sub(n))"));
+    }
+
+    #[test]
+    fn test_resulted_fail_info() {
+        #[derive(Debug, PartialEq)]
+        enum Error {
+            Parse,
+            Unmatched(FailInfo),
+        }
+        // `Checked` converts the structured information into the error as is.
+        impl From<FailInfo> for Error {
+            fn from(info: FailInfo) -> Self {
+                Error::Unmatched(info)
+            }
+        }
+        let line = line!() + 4;
+        let ans = |x: &str, y: u32| -> Result<u32, Error> {
+            qdo! {Checked::<Error> {
+                n <- x.parse::<u32>().map_err(|_| Error::Parse);
+                Some(m) <- Ok(n.checked_sub(y));
+                return m
+            }}
+        };
+        assert_eq!(ans("3", 1), Ok(2));
+        assert_eq!(ans("x", 1), Err(Error::Parse));
+        assert_eq!(
+            ans("1", 3),
+            Err(Error::Unmatched(FailInfo {
+                pattern: "Some(m)",
+                scrutinee: "Ok(n.checked_sub(y))",
+                file: file!(),
+                line,
+                column: 17,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_resulted_fail_from_string() {
+        // `fail_with` defaults to `fail`, so errors only need `From<String>`.
+        #[derive(Debug, PartialEq)]
+        struct Message(String);
+        impl From<String> for Message {
+            fn from(msg: String) -> Self {
+                Message(msg)
+            }
+        }
+        let ans = |r: Result<Option<u32>, Message>| -> Result<u32, Message> {
+            qdo! {Resulted {
+                Some(x) <- r;
+                return x
+            }}
+        };
+        assert_eq!(ans(Ok(Some(1))), Ok(1));
+        let Message(msg) = ans(Ok(None)).unwrap_err();
+        assert!(msg.ends_with("\n  expected: Some(x)\n  scrutinee: r"));
+    }
+
+    #[test]
+    fn test_direct_optioned_guard() {
+        let ans = |x: i64| {
+            qdo! {#[direct] Optioned {
+                y <- Some(x + 1);
+                guard y % 2 == 0;
+                return y / 2
+            }}
+        };
+        assert_eq!(ans(3), Some(2));
+        assert_eq!(ans(2), None);
+    }
+
+    #[test]
+    fn test_resulted_for() {
+        let check = |items: Vec<i64>| -> Result<Vec<i64>, String> {
+            qdo! {Resulted {
+                for x in items.iter() {
+                    guard_positive(*x)
+                };
+                ys <- for (i, x) in items.iter().enumerate() {
+                    y <- x.checked_mul(i as i64).ok_or("overflow".to_string());
+                    return y + 1
+                };
+                return ys
+            }}
+        };
+        fn guard_positive(x: i64) -> Result<(), String> {
+            if x > 0 {
+                Ok(())
+            } else {
+                Err(format!("{x} is not positive"))
+            }
+        }
+        assert_eq!(check(vec![3, 4, 5]), Ok(vec![1, 5, 11]));
+        assert_eq!(check(vec![3, -4, 0]), Err("-4 is not positive".to_string()));
+    }
+
+    #[test]
+    fn test_resulted_throw_catch() {
+        #[derive(Debug, PartialEq)]
+        enum Error {
+            NotFound(String),
+            Invalid(i64),
+        }
+        let lookup = |key: &str| -> Result<i64, Error> {
+            match key {
+                "one" => Ok(1),
+                "minus" => Ok(-1),
+                _ => Err(Error::NotFound(key.to_string())),
+            }
+        };
+        let ans = |key: &str| -> Result<i64, Error> {
+            qdo! {Resulted {
+                x <- try {
+                    x <- lookup(key);
+                    if x < 0 {
+                        throw Error::Invalid(x)
+                    };
+                    return x
+                } catch Error::NotFound(k) if k.is_empty() => {
+                    return 0
+                } catch Error::NotFound(_) => {
+                    return 100
+                };
+                return x * 2
+            }}
+        };
+        assert_eq!(ans("one"), Ok(2));
+        assert_eq!(ans(""), Ok(0));
+        assert_eq!(ans("two"), Ok(200));
+        assert_eq!(ans("minus"), Err(Error::Invalid(-1)));
+    }
+
+    #[test]
+    fn test_readered_config() {
+        #[derive(Clone)]
+        struct Config {
+            name: String,
+            indent: usize,
+        }
+        let line = |text: &'static str| {
+            Readered::<Config>::asks(move |c: &Config| format!("{}{text}", " ".repeat(c.indent)))
+        };
+        let greeting = qdo! {Readered::<Config> {
+            config <- Readered::<Config>::ask();
+            header <- line("Hello,");
+            body <- Readered::<Config>::local(
+                |c: &Config| Config { indent: c.indent + 2, ..c.clone() },
+                line("nice to meet you."),
+            );
+            return format!("{header} {}!\n{body}", config.name)
+        }};
+        let config = Config {
+            name: "Alice".to_string(),
+            indent: 1,
+        };
+        assert_eq!(
+            Reader::run(greeting, &config),
+            " Hello, Alice!\n   nice to meet you."
+        );
+    }
+
+    #[test]
+    fn test_stated_simulation() {
+        // A linear congruential generator, threading its seed as the state.
+        let next = || {
+            qdo! {Stated::<u64> {
+                seed <- Stated::<u64>::get();
+                let seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                // The continuation of the discarded statement refers to `seed`, so it owns `seed`.
+                Stated::<u64>::put(seed);
+                return seed >> 33
+            }}
+        };
+        let roll = || Stated::fmap(|r| r % 6 + 1, next());
+        let game = qdo! {Stated::<u64> {
+            a <- roll();
+            b <- roll();
+            rolls <- Stated::<u64>::traverse_iter(0..3, |_| roll());
+            return (a, b, rolls)
+        }};
+        let (a, b, rolls) = State::eval_state(game, 42);
+        assert!([a, b].iter().chain(&rolls).all(|r| (1..=6).contains(r)));
+        assert_eq!(rolls.len(), 3);
+        assert_eq!(State::exec_state(Stated::then(roll(), roll()), 42), {
+            let step = |s: u64| s.wrapping_mul(6364136223846793005).wrapping_add(1);
+            step(step(42))
+        });
+    }
+
+    #[test]
+    fn test_written_tell() {
+        let step = |name: &str, x: i64| -> (i64, Vec<String>) {
+            qdo! {Written::<Vec<String>> {
+                tell vec![format!("{name}: {x}")];
+                return x * 2
+            }}
+        };
+        let ans = qdo! {Written::<Vec<String>> {
+            x <- step("first", 1);
+            y <- step("second", x);
+            ~(z, log) <- Written::<Vec<String>>::listen(step("third", y));
+            tell vec![format!("{} entries so far", log.len())];
+            return z
+        }};
+        assert_eq!(
+            ans,
+            (
+                8,
+                vec![
+                    "first: 1".to_string(),
+                    "second: 2".to_string(),
+                    "third: 4".to_string(),
+                    "1 entries so far".to_string(),
+                ]
+            )
+        );
+        let counted = qdo! {Written::<(Sum<u32>, Option<String>)> {
+            tell (Sum(1), Some("a".to_string()));
+            Written::<(Sum<u32>, Option<String>)>::censor(
+                |(n, s)| (n, s.map(|s| s.to_uppercase())),
+                Written::<(Sum<u32>, Option<String>)>::tell((Sum(2), Some("b".to_string()))),
+            );
+            return ()
+        }};
+        assert_eq!(counted, ((), (Sum(3), Some("aB".to_string()))));
+    }
+
+    #[test]
+    fn test_validated_accumulates() {
+        #[derive(Debug, PartialEq)]
+        struct Form {
+            name: String,
+            age: u32,
+        }
+        let validate = |name: &str, age: &str| -> Result<Form, Vec<String>> {
+            let name = name.to_string();
+            let age = age.to_string();
+            qdo! {Validated::<Vec<String>> {
+                name <- if name.is_empty() {
+                    Err(vec!["name is empty".to_string()])
+                } else {
+                    Ok(name)
+                };
+                age <- age.parse::<u32>().map_err(|e| vec![format!("age: {e}")]);
+                return Form { name, age }
+            }}
+        };
+        assert_eq!(
+            validate("Alice", "20"),
+            Ok(Form {
+                name: "Alice".to_string(),
+                age: 20
+            })
+        );
+        assert_eq!(
+            validate("", "x"),
+            Err(vec![
+                "name is empty".to_string(),
+                "age: invalid digit found in string".to_string()
+            ])
+        );
+    }
 }
@@ -0,0 +1,1727 @@
+use super::types;
+use super::types::*;
+use super::vars::*;
+use proc_macro2::*;
+use quote::{quote, quote_spanned, ToTokens};
+use std::collections::HashSet;
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+use syn::{parse_quote, parse_quote_spanned, PatIdent, PatType};
+use syn::{Error, Lifetime, Pat, Token};
+
+fn mk_bind_cont(
+    namespace: Namespace,
+    counter: &mut u64,
+    irrefutable: bool,
+    p: Pat,
+    refutation: Refutation,
+    body: TokenStream,
+) -> TokenStream {
+    if refutation.is_trivial() && (irrefutable || is_simple_pat(&p)) {
+        let p = top_level_pat(&p);
+        return quote! { move |#p| #body };
+    }
+    let (p, ty) = split_ascription(p);
+    let var = fresh_var(counter);
+    let ty = ty.map(|ty| quote! { : #ty });
+    let cont = mk_match_cont(&namespace, Some((var.clone(), p)), refutation, body);
+    quote! { move |#var #ty| #cont }
+}
+
+/// `move` if the closure refers to a variable bound in the block.
+///
+/// Such variables are local to an enclosing continuation or block,
+/// which lazy namespaces (e.g. `Stated` or `Readered`) leave before running the closure.
+/// Other captures are borrowed as in plain closures, so that locals remain usable after the block.
+fn capture(
+    bound: &HashSet<Ident>,
+    params: &TokenStream,
+    body: &TokenStream,
+) -> Option<Token![move]> {
+    let captures_bound = syn::parse2::<syn::Expr>(quote! { |#params| #body }).map_or(true, |cls| {
+        ExprVarWalker::free_vars(&cls)
+            .iter()
+            .any(|v| bound.contains(v))
+    });
+    captures_bound.then(Default::default)
+}
+
+/// Pretty-prints the desugared expression with `prettyplease`, as the body of a wrapping function.
+fn pretty(expr: TokenStream) -> Result<String, syn::Error> {
+    let file: syn::File = syn::parse2(quote! { fn __qdo() { #expr } })?;
+    let printed = prettyplease::unparse(&file);
+    let lines = printed.lines().collect::<Vec<_>>();
+    let body = lines[1..lines.len() - 1]
+        .iter()
+        .map(|line| line.strip_prefix("    ").unwrap_or(line))
+        .collect::<Vec<_>>();
+    Ok(body.join("\n"))
+}
+
+impl Namespace {
+    /// `NAMESPACE::name`, spanned to the statement it is generated for.
+    fn method(&self, name: &str, span: Span) -> TokenStream {
+        let name = Ident::new(name, span);
+        quote_spanned! {span=> #self::#name }
+    }
+}
+
+fn fresh_var(counter: &mut u64) -> syn::Ident {
+    *counter += 1;
+    Ident::new(&format!("__qdo_arg_{}", counter), Span::call_site())
+}
+
+/// Variables bound by the statements and visible after them, in order of their last binding.
+fn bound_vars(statements: &[DoStatement]) -> Vec<Ident> {
+    let mut vars = Vec::new();
+    for stmt in statements {
+        let pat = match stmt {
+            DoStatement::Bind(Bind { pat, .. }) | DoStatement::Let(Let { pat, .. }) => pat,
+            _ => continue,
+        };
+        for v in PatVarWalker::vars(pat) {
+            // Later bindings shadow earlier ones.
+            vars.retain(|w| w != &v);
+            vars.push(v);
+        }
+    }
+    vars
+}
+
+impl QDo {
+    pub fn desugar(self) -> Result<TokenStream, syn::Error> {
+        let notes = match self.explain {
+            None => Vec::new(),
+            Some(_) => self.explain()?,
+        };
+        if let (Some(Explain::Strict), Some(note)) = (self.explain, notes.first()) {
+            return Err(Error::new(note.span, &note.message));
+        }
+        let body = match self.mode {
+            Mode::Auto => self.desugar_auto(),
+            Mode::Monadic => self.desugar_monad(),
+            Mode::Applicative => self.desugar_strict_applicative(),
+            Mode::Direct => self.desugar_direct(),
+        }?;
+        if notes.is_empty() {
+            return Ok(body);
+        }
+        let warnings = notes.iter().map(Note::to_warning);
+        Ok(quote! { { #(#warnings)* #body } })
+    }
+
+    /// The strategy [`QDo::desugar`] takes for the block.
+    /// [`Mode::Auto`] resolves to [`Mode::Monadic`] if `ApplicativeDo`-style segmentation gains nothing.
+    pub fn resolve_mode(&self) -> Result<Mode, syn::Error> {
+        match self.mode {
+            Mode::Auto if self.clone().plan()?.is_sequential() => Ok(Mode::Monadic),
+            mode => Ok(mode),
+        }
+    }
+
+    /// Desugars the block and pretty-prints the result, headed by a comment on the chosen strategy.
+    pub fn stringify(self) -> Result<String, syn::Error> {
+        let mode = match (self.mode, self.resolve_mode()?) {
+            (Mode::Auto, Mode::Monadic) => "auto (monadic)",
+            (Mode::Auto, _) => "auto (applicative)",
+            (mode, _) => mode.name(),
+        };
+        Ok(format!("// mode: {mode}\n{}", pretty(self.desugar()?)?))
+    }
+
+    /// Collects notes for `#[explain]`: why statements require `and_then` in the applicative modes,
+    /// and `return`s in the middle of the block, which don't exit it.
+    fn explain(&self) -> Result<Vec<Note>, syn::Error> {
+        let mut notes = Vec::new();
+        if matches!(self.mode, Mode::Auto | Mode::Applicative) {
+            let plan = self.clone().plan()?;
+            notes.extend(plan.binds.iter().map(|reason| Note {
+                span: reason.span(),
+                message: reason.message(),
+            }));
+        }
+        // Values of all the statements but the last one are discarded, unless followed by `;`.
+        let discarded = match self.statements.split_last() {
+            Some((_, init)) if !self.trailing_semi => init,
+            _ => &self.statements,
+        };
+        notes.extend(discarded.iter().filter_map(|stmt| {
+            match stmt {
+                DoStatement::Return(Return { return_token, .. }) => Some(Note {
+                    span: return_token.span,
+                    message:
+                        "`return` in the middle of a block does not exit it; its value is discarded"
+                            .to_string(),
+                }),
+                _ => None,
+            }
+        }));
+        Ok(notes)
+    }
+
+    /// Desugars the block with `ApplicativeDo`-style segmentation,
+    /// falling back to [`QDo::desugar_monad`] if it gains nothing.
+    pub fn desugar_auto(self) -> Result<TokenStream, syn::Error> {
+        if self.has_then() {
+            return self.desugar_then();
+        }
+        let namespace = self.namespace.clone();
+        let plan = self.clone().plan()?;
+        if plan.is_sequential() {
+            self.desugar_monad()
+        } else {
+            Ok(plan.desugar(&namespace))
+        }
+    }
+
+    /// Desugars the block only with `NAMESPACE::fmap`, `NAMESPACE::zip_with` and `NAMESPACE::guard`,
+    /// rejecting any statement that requires `NAMESPACE::and_then`.
+    pub fn desugar_strict_applicative(self) -> Result<TokenStream, syn::Error> {
+        let namespace = self.namespace.clone();
+        let plan = self.plan()?;
+        match plan.binds.first() {
+            Some(reason) => Err(reason.to_error()),
+            None => Ok(plan.desugar(&namespace)),
+        }
+    }
+
+    /// Chains every statement with `NAMESPACE::and_then`.
+    pub fn desugar_monad(self) -> Result<TokenStream, syn::Error> {
+        if self.has_then() {
+            return self.desugar_then();
+        }
+        let QDo {
+            mode,
+            namespace,
+            mut statements,
+            trailing_semi,
+            ..
+        } = self;
+
+        let counter = &mut 0;
+        let bound = bound_vars(&statements).into_iter().collect();
+
+        let last = if trailing_semi {
+            quote! { #namespace::pure(()) }
+        } else {
+            let last = statements.pop().unwrap();
+            let span = last.span();
+            match last {
+                DoStatement::Expr(expr) => expr.lower(&namespace, mode)?.into_token_stream(),
+                DoStatement::Return(Return { expr, .. }) => {
+                    let pure = namespace.method("pure", span);
+                    quote_spanned! {span=> #pure(#expr) }
+                }
+                t => {
+                    return Err(Error::new_spanned(
+                        t,
+                        "Expected an expression or return statement at the last",
+                    ))
+                }
+            }
+        };
+        statements.into_iter().try_rfold(last, |acc, stmt| {
+            let span = stmt.span();
+            let and_then = namespace.method("and_then", span);
+            match stmt {
+                DoStatement::Expr(expr) => {
+                    let expr = expr.lower(&namespace, mode)?;
+                    let capture = capture(&bound, &quote! { _ }, &acc);
+                    Ok(quote_spanned! {span=> #and_then(#expr, #capture |_| #acc) })
+                }
+                DoStatement::Return(Return { expr, .. }) => {
+                    let pure = namespace.method("pure", span);
+                    let capture = capture(&bound, &quote! { _ }, &acc);
+                    Ok(quote_spanned! {span=> #and_then(#pure(#expr), #capture |_| #acc) })
+                }
+                DoStatement::Let(Let {
+                    pat,
+                    expr,
+                    else_branch: None,
+                    ..
+                }) => Ok(quote! { {let #pat = #expr; #acc} }),
+                DoStatement::Let(Let {
+                    pat,
+                    expr,
+                    else_branch: Some((_, alt)),
+                    ..
+                }) => {
+                    let alt = alt.lower(&namespace, mode)?;
+                    Ok(quote_spanned! {span=>
+                        match #expr {
+                            #pat => #acc,
+                            _ => #alt,
+                        }
+                    })
+                }
+                DoStatement::Bind(Bind {
+                    irrefutable,
+                    pat,
+                    body,
+                    guard,
+                    else_branch,
+                    ..
+                }) => {
+                    let refutation = Refutation::new(guard, else_branch, &body, &namespace, mode)?;
+                    let body = body.lower_bind(&namespace, mode)?;
+                    let closure = mk_bind_cont(
+                        namespace.clone(),
+                        counter,
+                        irrefutable.is_some(),
+                        pat,
+                        refutation,
+                        acc,
+                    );
+                    Ok(quote_spanned! {span=>
+                        #and_then(#body, #closure)
+                    })
+                }
+                DoStatement::Guard(Guard { cond, .. }) => {
+                    let guard = namespace.method("guard", span);
+                    Ok(quote_spanned! {span=>
+                        #and_then(#guard(#cond), move |()| #acc)
+                    })
+                }
+                DoStatement::Then(_) => unreachable!("`then` is desugared by `desugar_then`"),
+            }
+        })
+    }
+
+    fn has_then(&self) -> bool {
+        self.statements
+            .iter()
+            .any(|stmt| matches!(stmt, DoStatement::Then(_)))
+    }
+
+    /// Desugars the block at the last `then` statement.
+    ///
+    /// The preceding statements return the tuple of variables bound so far,
+    /// which is transformed by `NAMESPACE::take`, `NAMESPACE::sort_by` or `NAMESPACE::group_by`
+    /// and rebound for the rest of the block with `NAMESPACE::and_then`.
+    fn desugar_then(self) -> Result<TokenStream, syn::Error> {
+        let QDo {
+            mode,
+            namespace,
+            mut statements,
+            trailing_semi,
+            ..
+        } = self;
+        let i = statements
+            .iter()
+            .rposition(|stmt| matches!(stmt, DoStatement::Then(_)))
+            .unwrap();
+        let rest = statements.split_off(i + 1);
+        let Some(DoStatement::Then(Then {
+            then_token,
+            transform,
+        })) = statements.pop()
+        else {
+            unreachable!()
+        };
+        let span = then_token.span;
+        if rest.is_empty() && !trailing_semi {
+            return Err(Error::new(
+                span,
+                "`then` cannot be the last statement; `return` the results after it",
+            ));
+        }
+        let vars = bound_vars(&statements);
+        let tuple = quote! { (#(#vars,)*) };
+        statements.push(DoStatement::Return(Return {
+            return_token: Token![return](span),
+            expr: parse_quote! { #tuple },
+        }));
+        let block = |statements, trailing_semi| {
+            QDo {
+                mode,
+                explain: None,
+                namespace: namespace.clone(),
+                statements,
+                trailing_semi,
+            }
+            .desugar()
+        };
+        let init = block(statements, false)?;
+        let rest = block(rest, trailing_semi)?;
+
+        let counter = &mut 0;
+        // Compares the keys evaluated with the variables bound to references to the results.
+        let mut compare = |key: &syn::Expr| {
+            let a = fresh_var(counter);
+            let b = fresh_var(counter);
+            quote_spanned! {key.span()=>
+                |#a, #b| ::core::cmp::Ord::cmp(
+                    &{
+                        #[allow(unused_variables)]
+                        let #tuple = #a;
+                        #key
+                    },
+                    &{
+                        #[allow(unused_variables)]
+                        let #tuple = #b;
+                        #key
+                    },
+                )
+            }
+        };
+        let (transformed, grouped) = match transform {
+            Transform::Take(_, n) => {
+                let take = namespace.method("take", span);
+                (quote_spanned! {span=> #take(#n, #init) }, false)
+            }
+            Transform::SortBy(_, key) => {
+                let sort_by = namespace.method("sort_by", span);
+                let compare = compare(&key);
+                (quote_spanned! {span=> #sort_by(#compare, #init) }, false)
+            }
+            Transform::GroupBy { key, using, .. } => {
+                let group_by = using.map_or_else(
+                    || namespace.method("group_by", span),
+                    |(_, f)| f.into_token_stream(),
+                );
+                let compare = compare(&key);
+                (quote_spanned! {span=> #group_by(#compare, #init) }, true)
+            }
+        };
+        let arg = fresh_var(counter);
+        let rebind = if !grouped {
+            quote! {
+                #[allow(unused_variables)]
+                let #tuple = #arg;
+            }
+        } else if vars.is_empty() {
+            quote! { let _ = #arg; }
+        } else {
+            // Each variable is rebound to a `Vec` of its values in the group.
+            let groups = fresh_var(counter);
+            let index = (0..vars.len()).map(syn::Index::from);
+            let empty = vars.iter().map(|_| quote! { ::std::vec::Vec::new() });
+            quote! {
+                #[allow(unused_variables)]
+                let #tuple = {
+                    let mut #groups = (#(#empty,)*);
+                    for #tuple in #arg {
+                        #(#groups.#index.push(#vars);)*
+                    }
+                    #groups
+                };
+            }
+        };
+        let and_then = namespace.method("and_then", span);
+        Ok(quote_spanned! {span=>
+            #and_then(#transformed, move |#arg| {
+                #rebind
+                #rest
+            })
+        })
+    }
+
+    /// Evaluates statements one by one in a labeled block, in terms of
+    /// `NAMESPACE::branch` and `NAMESPACE::from_residual` instead of closures,
+    /// just as `?`-operator does.
+    pub fn desugar_direct(self) -> Result<TokenStream, syn::Error> {
+        let QDo {
+            mode,
+            namespace,
+            mut statements,
+            trailing_semi,
+            ..
+        } = self;
+        // Labels of nested blocks must be distinct to avoid shadowing.
+        let depth = statements.iter().map(DoStatement::depth).max().unwrap_or(0);
+        let label = Lifetime::new(&format!("'__qdo{depth}"), Span::mixed_site());
+        let eval = |expr: &dyn ToTokens, span: Span| {
+            let branch = namespace.method("branch", span);
+            let from_residual = namespace.method("from_residual", span);
+            quote_spanned! {span=>
+                match #branch(#expr) {
+                    ::core::ops::ControlFlow::Continue(v) => v,
+                    ::core::ops::ControlFlow::Break(r) => break #label #from_residual(r),
+                }
+            }
+        };
+
+        let last = if trailing_semi {
+            quote! { #namespace::pure(()) }
+        } else {
+            let last = statements.pop().unwrap();
+            let span = last.span();
+            match last {
+                DoStatement::Expr(expr) => expr.lower(&namespace, mode)?.into_token_stream(),
+                DoStatement::Return(Return { expr, .. }) => {
+                    let pure = namespace.method("pure", span);
+                    quote_spanned! {span=> #pure(#expr) }
+                }
+                t => {
+                    return Err(Error::new_spanned(
+                        t,
+                        "Expected an expression or return statement at the last",
+                    ))
+                }
+            }
+        };
+        let stmts = statements.into_iter().map(|stmt| {
+            let span = stmt.span();
+            Ok(match stmt {
+                DoStatement::Expr(expr) => {
+                    let expr = eval(&expr.lower(&namespace, mode)?, span);
+                    quote! { let _ = #expr; }
+                }
+                DoStatement::Return(Return { expr, .. }) => quote! { let _ = #expr; },
+                DoStatement::Let(Let {
+                    pat,
+                    expr,
+                    else_branch: None,
+                    ..
+                }) => quote! { let #pat = #expr; },
+                DoStatement::Let(Let {
+                    pat,
+                    expr,
+                    else_branch: Some((_, alt)),
+                    ..
+                }) => {
+                    let alt = alt.lower(&namespace, mode)?;
+                    quote! { let #pat = (#expr) else { break #label #alt }; }
+                }
+                DoStatement::Bind(Bind {
+                    irrefutable,
+                    pat,
+                    body,
+                    guard,
+                    else_branch,
+                    ..
+                }) => {
+                    let refutation = Refutation::new(guard, else_branch, &body, &namespace, mode)?;
+                    let body = eval(&body.lower_bind(&namespace, mode)?, span);
+                    let fallback = refutation.fallback(&namespace, &pat);
+                    let simple = irrefutable.is_some() || is_simple_pat(&pat);
+                    let pat = top_level_pat(&pat);
+                    let bind = if simple {
+                        quote! { let #pat = #body; }
+                    } else {
+                        quote! { let #pat = (#body) else { break #label #fallback }; }
+                    };
+                    let guard = refutation.guard.map(|guard| {
+                        quote! { if !(#guard) { break #label #fallback } }
+                    });
+                    quote! { #bind #guard }
+                }
+                DoStatement::Guard(Guard { cond, .. }) => {
+                    let guard = namespace.method("guard", span);
+                    let expr = eval(&quote_spanned! {span=> #guard(#cond) }, span);
+                    quote! { let _ = #expr; }
+                }
+                DoStatement::Then(Then { then_token, .. }) => {
+                    return Err(Error::new(
+                        then_token.span,
+                        "`then` is not supported in `#[direct]` blocks",
+                    ));
+                }
+            })
+        });
+        let stmts = stmts.collect::<Result<Vec<_>, Error>>()?;
+        Ok(quote! {
+            #label: {
+                #(#stmts)*
+                #last
+            }
+        })
+    }
+
+    /// Splits statements into [`Segment`]s of independent statements.
+    fn plan(self) -> Result<Plan, syn::Error> {
+        use DoStatement::*;
+        let QDo {
+            mode,
+            namespace,
+            mut statements,
+            trailing_semi,
+            ..
+        } = self;
+        let bound = bound_vars(&statements).into_iter().collect();
+        let mut binds = Vec::new();
+        let mut kind = if trailing_semi {
+            LastKind::Unit
+        } else {
+            let last = statements.pop().unwrap();
+            let span = last.span();
+            match last {
+                Expr(expr) => LastKind::Expr(expr.lower(&namespace, mode)?),
+                Return(types::Return { expr, .. }) => LastKind::Return(expr, span),
+                t => {
+                    return Err(Error::new_spanned(
+                        t,
+                        "Expected an expression or return statement at the last",
+                    ))
+                }
+            }
+        };
+
+        let mut segments = Vec::new();
+        let mut current = Segment::default();
+        let mut vars = GroupVars::default();
+        // `let`s floated out of the current group, to be put at the beginning of the continuation.
+        let mut floated = Vec::new();
+        for stmt in statements {
+            let span = stmt.span();
+            let mut refutation = Refutation::default();
+            // The reason why the binding is refutable, if any.
+            let mut refuted = None;
+            let (pat, effect, refutable) = match stmt {
+                Let(types::Let {
+                    pat,
+                    expr,
+                    else_branch: None,
+                    ..
+                }) => (pat, Err(expr), false),
+                // `let pat = e else { alt }` is regarded as `pat <- NAMESPACE::pure(e) else { alt }`.
+                Let(types::Let {
+                    pat,
+                    expr,
+                    else_branch: Some((else_token, alt)),
+                    ..
+                }) => {
+                    refuted = Some(BindReason::Else(else_token));
+                    refutation.fallback = Some(alt.lower(&namespace, mode)?);
+                    let pure = namespace.method("pure", span);
+                    (pat, Ok(parse_quote_spanned! {span=> #pure(#expr) }), true)
+                }
+                Return(types::Return { expr, .. }) => (parse_quote! { _ }, Err(expr), false),
+                Bind(types::Bind {
+                    irrefutable,
+                    pat,
+                    body,
+                    guard,
+                    else_branch,
+                    ..
+                }) => {
+                    refuted = match (&else_branch, &guard) {
+                        (Some((else_token, _)), _) => Some(BindReason::Else(*else_token)),
+                        (None, Some((if_token, _))) => Some(BindReason::Guarded(*if_token)),
+                        (None, None) if irrefutable.is_none() && !is_simple_pat(&pat) => {
+                            Some(BindReason::Refutable(pat.clone()))
+                        }
+                        (None, None) => None,
+                    };
+                    refutation = Refutation::new(guard, else_branch, &body, &namespace, mode)?;
+                    let refutable = refuted.is_some();
+                    (pat, Ok(body.lower_bind(&namespace, mode)?), refutable)
+                }
+                Expr(expr) => (parse_quote! { _ }, Ok(expr.lower(&namespace, mode)?), false),
+                Guard(types::Guard { cond, .. }) => {
+                    let guard = namespace.method("guard", span);
+                    (
+                        parse_quote! { _ },
+                        Ok(parse_quote_spanned! {span=> #guard(#cond) }),
+                        false,
+                    )
+                }
+                // Only recorded, as blocks with `then` are split before planned.
+                Then(types::Then { then_token, .. }) => {
+                    binds.push(BindReason::Transform(then_token));
+                    continue;
+                }
+            };
+            let mut free = ExprVarWalker::free_vars(effect.as_ref().unwrap_or_else(|e| e));
+            let binders = PatVarWalker::vars(&pat);
+            if let Some(guard) = &refutation.guard {
+                // The guard is evaluated after the whole group is bound,
+                // but regarded as a part of the statement for simplicity.
+                let guard_free = ExprVarWalker::free_vars(guard);
+                free.extend(guard_free.into_iter().filter(|v| !binders.contains(v)));
+            }
+            match effect {
+                Err(expr) if current.group.is_empty() => {
+                    current.lets.push(quote! { let #pat = #expr });
+                }
+                Err(expr) => {
+                    floated.push(quote! { let #pat = #expr });
+                    vars.floated_free.extend(free);
+                    vars.floated_bound.extend(binders);
+                }
+                Ok(effect) => {
+                    let reason = vars
+                        .dependency(&free)
+                        .map(|v| BindReason::Depends(v.clone()))
+                        .or_else(|| {
+                            vars.shadowed(&binders)
+                                .map(|v| BindReason::Shadows(v.clone()))
+                        });
+                    if let Some(reason) = reason {
+                        current.closed_by = Some(reason.span());
+                        binds.push(reason);
+                        segments.push(current.close(std::mem::take(&mut floated)));
+                        vars = GroupVars::default();
+                    }
+                    vars.bound.extend(binders);
+                    if refutable {
+                        current.closed_by = refuted.as_ref().map(BindReason::span);
+                        binds.extend(refuted);
+                        current.refutable = true;
+                        current.refutation = refutation;
+                    }
+                    current.group.push((pat, effect.into_token_stream(), span));
+                    if current.refutable {
+                        segments.push(current.close(std::mem::take(&mut floated)));
+                        vars = GroupVars::default();
+                    }
+                }
+            }
+        }
+        // In `#[applicative]` blocks, the last expression can be sequenced after the last group
+        // with `NAMESPACE::then` if it refers to nothing bound there.
+        // Otherwise it is chained with `and_then`, so that it is evaluated only after the group succeeds.
+        if let (Mode::Applicative, LastKind::Expr(expr)) = (mode, &kind) {
+            let free = ExprVarWalker::free_vars(expr);
+            if !current.group.is_empty() && floated.is_empty() && vars.dependency(&free).is_none() {
+                kind = LastKind::Then(expr.clone());
+            }
+        }
+        let lets = if current.group.is_empty() {
+            current.lets
+        } else {
+            segments.push(current);
+            floated
+        };
+        match &kind {
+            // A sole expression needs no `and_then`.
+            LastKind::Expr(expr) if !segments.is_empty() => {
+                let seg = segments.last_mut().unwrap();
+                seg.closed_by.get_or_insert(expr.span());
+                binds.push(BindReason::LastExpr(expr.clone()));
+            }
+            _ => {}
+        }
+
+        Ok(Plan {
+            segments,
+            last: Last { lets, kind },
+            binds,
+            bound,
+        })
+    }
+}
+
+impl QComp {
+    /// Desugars the comprehension as a `qdo` block ending with `return head`.
+    ///
+    /// Parallel branches are desugared into blocks returning tuples of their binders,
+    /// which are joined with `NAMESPACE::zip_with` and mapped to `head` with `NAMESPACE::fmap`.
+    pub fn desugar(self) -> Result<TokenStream, syn::Error> {
+        let QComp {
+            mode,
+            namespace,
+            head,
+            mut branches,
+        } = self;
+        let span = head.span();
+        let block = |mut statements: Vec<DoStatement>, expr: syn::Expr| {
+            statements.push(DoStatement::Return(Return {
+                return_token: Token![return](span),
+                expr,
+            }));
+            QDo {
+                mode,
+                explain: None,
+                namespace: namespace.clone(),
+                statements,
+                trailing_semi: false,
+            }
+            .desugar()
+        };
+        if branches.len() == 1 {
+            return block(branches.pop().unwrap(), head);
+        }
+        let counter = &mut 0;
+        let mut joined: Option<(TokenStream, TokenStream)> = None;
+        let mut bound = HashSet::new();
+        for statements in branches {
+            let vars = bound_vars(&statements);
+            bound.extend(vars.iter().cloned());
+            let body = block(statements, parse_quote! { (#(#vars,)*) })?;
+            let pat = quote! { (#(#vars,)*) };
+            joined = Some(match joined {
+                None => (body, pat),
+                Some((acc, pats)) => {
+                    let a = fresh_var(counter);
+                    let b = fresh_var(counter);
+                    let zip_with = namespace.method("zip_with", span);
+                    (
+                        quote_spanned! {span=> #zip_with(|#a, #b| (#a, #b), #acc, #body) },
+                        quote! { (#pats, #pat) },
+                    )
+                }
+            });
+        }
+        let (body, pat) = joined.unwrap();
+        let fmap = namespace.method("fmap", span);
+        let arg = fresh_var(counter);
+        // Binders only used inside their own branch are carried to the head as well.
+        let result = quote! {
+            {
+                #[allow(unused_variables)]
+                let #pat = #arg;
+                #head
+            }
+        };
+        let capture = capture(&bound, &arg.to_token_stream(), &result);
+        Ok(quote_spanned! {span=> #fmap(#capture |#arg| #result, #body) })
+    }
+}
+
+impl DoExpr {
+    /// Desugars do-blocks in the arms with the namespace and mode of the enclosing block.
+    fn lower(self, namespace: &Namespace, mode: Mode) -> Result<syn::Expr, Error> {
+        match self {
+            // Parentheses only mark plain Rust control flow, whose arms are not do-blocks.
+            DoExpr::Expr(syn::Expr::Paren(syn::ExprParen { expr, .. }))
+                if matches!(*expr, syn::Expr::If(_) | syn::Expr::Match(_)) =>
+            {
+                Ok(*expr)
+            }
+            DoExpr::Expr(expr) => Ok(expr),
+            DoExpr::If(i) => i.lower(namespace, mode),
+            DoExpr::Match(DoMatch {
+                match_token,
+                expr,
+                arms,
+                ..
+            }) => {
+                let arms = arms
+                    .into_iter()
+                    .map(|arm| arm.lower(namespace, mode))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                syn::parse2(quote! { #match_token #expr { #(#arms)* } })
+            }
+            DoExpr::For(f) => f.lower(namespace, mode, "for_each"),
+            DoExpr::Throw(Throw { throw_token, expr }) => {
+                let span = throw_token.span;
+                let throw_error = namespace.method("throw_error", span);
+                Ok(parse_quote_spanned! {span=> #throw_error(#expr) })
+            }
+            DoExpr::Tell(Tell { tell_token, expr }) => {
+                let span = tell_token.span;
+                let tell = namespace.method("tell", span);
+                Ok(parse_quote_spanned! {span=> #tell(#expr) })
+            }
+            DoExpr::Try(DoTry {
+                try_token,
+                body,
+                catches,
+            }) => {
+                let span = try_token.span;
+                let catch_error = namespace.method("catch_error", span);
+                let throw_error = namespace.method("throw_error", span);
+                let body = body.lower(namespace, mode)?;
+                let arms = catches
+                    .into_iter()
+                    .map(|(_, arm)| arm.lower(namespace, mode))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let err = Ident::new("__qdo_err", Span::mixed_site());
+                syn::parse2(quote_spanned! {span=>
+                    #catch_error(#body, move |#err| match #err {
+                        #(#arms)*
+                        // Errors not caught by any arm are rethrown.
+                        #[allow(unreachable_patterns)]
+                        #err => #throw_error(#err),
+                    })
+                })
+            }
+        }
+    }
+}
+
+impl DoStatement {
+    /// The depth of do-blocks nested in the statement.
+    fn depth(&self) -> usize {
+        match self {
+            DoStatement::Expr(expr) => expr.depth(),
+            DoStatement::Bind(Bind {
+                body, else_branch, ..
+            }) => else_branch
+                .iter()
+                .map(|(_, alt)| alt.depth())
+                .fold(body.depth(), usize::max),
+            DoStatement::Let(Let {
+                else_branch: Some((_, alt)),
+                ..
+            }) => alt.depth(),
+            _ => 0,
+        }
+    }
+}
+
+impl DoExpr {
+    fn depth(&self) -> usize {
+        match self {
+            DoExpr::Expr(_) => 0,
+            DoExpr::If(i) => i.depth(),
+            DoExpr::Match(DoMatch { arms, .. }) => {
+                arms.iter().map(|arm| arm.body.depth()).max().unwrap_or(0)
+            }
+            DoExpr::For(DoFor { body, .. }) => body.depth(),
+            DoExpr::Throw(_) | DoExpr::Tell(_) => 0,
+            DoExpr::Try(DoTry { body, catches, .. }) => catches
+                .iter()
+                .map(|(_, arm)| arm.body.depth())
+                .fold(body.depth(), usize::max),
+        }
+    }
+
+    /// Desugars the right-hand side of a binding,
+    /// where `for` collects the results with `NAMESPACE::traverse_iter`.
+    fn lower_bind(self, namespace: &Namespace, mode: Mode) -> Result<syn::Expr, Error> {
+        match self {
+            DoExpr::For(f) => f.lower(namespace, mode, "traverse_iter"),
+            expr => expr.lower(namespace, mode),
+        }
+    }
+}
+
+impl DoBlock {
+    fn depth(&self) -> usize {
+        1 + self
+            .statements
+            .iter()
+            .map(DoStatement::depth)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl DoIf {
+    fn depth(&self) -> usize {
+        let else_depth = match &self.else_branch {
+            None => 0,
+            Some((_, DoElse::Block(block))) => block.depth(),
+            Some((_, DoElse::If(i))) => i.depth(),
+        };
+        self.then_branch.depth().max(else_depth)
+    }
+
+    /// Desugars the branches; a missing `else` branch defaults to `NAMESPACE::pure(())`.
+    fn lower(self, namespace: &Namespace, mode: Mode) -> Result<syn::Expr, Error> {
+        let DoIf {
+            if_token,
+            cond,
+            then_branch,
+            else_branch,
+        } = self;
+        let then_branch = then_branch.lower(namespace, mode)?;
+        let else_branch = match else_branch {
+            None => {
+                let pure = namespace.method("pure", if_token.span);
+                quote! { { #pure(()) } }
+            }
+            Some((_, DoElse::Block(block))) => block.lower(namespace, mode)?,
+            Some((_, DoElse::If(i))) => i.lower(namespace, mode)?.into_token_stream(),
+        };
+        syn::parse2(quote! { #if_token #cond #then_branch else #else_branch })
+    }
+}
+
+impl DoArm {
+    fn lower(self, namespace: &Namespace, mode: Mode) -> Result<TokenStream, Error> {
+        let DoArm {
+            pat, guard, body, ..
+        } = self;
+        let guard = guard.map(|(if_token, cond)| quote! { #if_token #cond });
+        let body = body.lower(namespace, mode)?;
+        Ok(quote! { #pat #guard => #body, })
+    }
+}
+
+impl DoFor {
+    /// Desugars into `NAMESPACE::for_each` or `NAMESPACE::traverse_iter`, given as `method`.
+    fn lower(self, namespace: &Namespace, mode: Mode, method: &str) -> Result<syn::Expr, Error> {
+        let DoFor {
+            for_token,
+            pat,
+            expr,
+            body,
+            ..
+        } = self;
+        let body = body.lower(namespace, mode)?;
+        let pat = top_level_pat(&pat);
+        let span = for_token.span;
+        let method = namespace.method(method, span);
+        syn::parse2(quote_spanned! {span=> #method(#expr, move |#pat| #body) })
+    }
+}
+
+impl DoBlock {
+    /// Desugars the block as a `qdo` block on its own.
+    fn lower(self, namespace: &Namespace, mode: Mode) -> Result<TokenStream, Error> {
+        let DoBlock {
+            statements,
+            trailing_semi,
+            ..
+        } = self;
+        let body = QDo {
+            mode,
+            explain: None,
+            namespace: namespace.clone(),
+            statements,
+            trailing_semi,
+        }
+        .desugar()?;
+        Ok(quote! { { #body } })
+    }
+}
+
+/// Statements of a `qdo` block, split into independent segments.
+struct Plan {
+    segments: Vec<Segment>,
+    last: Last,
+    /// Reasons why `and_then` is needed, in order of appearance.
+    binds: Vec<BindReason>,
+    /// Variables bound by the statements.
+    bound: HashSet<Ident>,
+}
+
+/// A diagnostic on the block, reported by `#[explain]`.
+struct Note {
+    span: Span,
+    message: String,
+}
+
+impl Note {
+    /// Reports the note as a warning on stable Rust, by calling a deprecated function.
+    fn to_warning(&self) -> TokenStream {
+        let name = Ident::new("qdo_explain", Span::mixed_site().located_at(self.span));
+        let message = &self.message;
+        quote_spanned! {self.span=>
+            {
+                #[deprecated(note = #message)]
+                fn #name() {}
+                #name();
+            }
+        }
+    }
+}
+
+/// A reason why a statement requires `NAMESPACE::and_then`.
+enum BindReason {
+    /// The statement refers to a variable bound earlier in the same group.
+    Depends(Ident),
+    /// The statement rebinds a variable a preceding `let` refers to.
+    Shadows(Ident),
+    /// The binding pattern is refutable, so it needs `NAMESPACE::fail_with`.
+    Refutable(Pat),
+    /// The binding has an `else` branch.
+    Else(Token![else]),
+    /// The binding has a pattern guard.
+    Guarded(Token![if]),
+    /// The last statement is not a `return`.
+    LastExpr(syn::Expr),
+    /// `then` transforms the results of all the preceding statements.
+    Transform(keywords::then),
+}
+
+impl BindReason {
+    fn message(&self) -> String {
+        match self {
+            BindReason::Depends(v) => {
+                format!("`{v}` is bound by a preceding statement, so this statement requires `and_then`")
+            }
+            BindReason::Shadows(v) => {
+                format!("rebinding `{v}` used by a preceding `let` requires `and_then`")
+            }
+            BindReason::Refutable(p) => format!(
+                "refutable pattern `{}` requires `and_then` and `fail_with`; prefix it with `~` if it is irrefutable",
+                p.to_token_stream()
+            ),
+            BindReason::Else(_) => "binding with `else` requires `and_then`".to_string(),
+            BindReason::Guarded(_) => {
+                "binding with a pattern guard requires `and_then` and `fail_with`".to_string()
+            }
+            BindReason::LastExpr(_) => {
+                "the last statement must be `return expr` to avoid `and_then`".to_string()
+            }
+            BindReason::Transform(_) => {
+                "`then` transforms the results of all the preceding statements, so it requires `and_then`"
+                    .to_string()
+            }
+        }
+    }
+
+    fn span(&self) -> Span {
+        match self {
+            BindReason::Depends(v) | BindReason::Shadows(v) => v.span(),
+            BindReason::Refutable(p) => p.span(),
+            BindReason::Else(e) => e.span,
+            BindReason::Guarded(i) => i.span,
+            BindReason::LastExpr(e) => e.span(),
+            BindReason::Transform(t) => t.span,
+        }
+    }
+
+    fn to_error(&self) -> Error {
+        let message = self.message();
+        match self {
+            BindReason::Depends(_) | BindReason::Shadows(_) => Error::new(self.span(), message),
+            BindReason::Refutable(p) => Error::new_spanned(p, message),
+            BindReason::Else(e) => Error::new_spanned(e, message),
+            BindReason::Guarded(i) => Error::new_spanned(i, message),
+            BindReason::LastExpr(e) => Error::new_spanned(e, message),
+            BindReason::Transform(t) => Error::new_spanned(t, message),
+        }
+    }
+}
+
+impl Plan {
+    /// Desugars the block with `ApplicativeDo`-style segmentation.
+    ///
+    /// Statements are split into groups of mutually independent statements.
+    /// Each group is joined with `NAMESPACE::zip_with`, and groups are chained with `NAMESPACE::and_then`.
+    /// If the whole block consists of a single group ending with `return`, no `and_then` is needed at all.
+    fn desugar(self, namespace: &Namespace) -> TokenStream {
+        let Plan {
+            segments,
+            last: Last { lets, kind },
+            bound,
+            ..
+        } = self;
+        let counter = &mut 0;
+        let mut segments = segments.into_iter().rev();
+        let mut acc = match (segments.next(), kind) {
+            (Some(seg), LastKind::Then(expr)) => seg.then(namespace, counter, expr),
+            (Some(seg), kind @ (LastKind::Return(..) | LastKind::Unit)) if !seg.refutable => {
+                let (result, span) = match kind {
+                    LastKind::Return(expr, span) => (expr.into_token_stream(), Some(span)),
+                    _ => (quote! { () }, None),
+                };
+                let result = quote! { { #(#lets;)* #result } };
+                seg.fmap(namespace, counter, span, &bound, result)
+            }
+            (seg, kind) => {
+                let result = match kind {
+                    LastKind::Return(expr, span) => {
+                        let pure = namespace.method("pure", span);
+                        quote_spanned! {span=> #pure(#expr) }
+                    }
+                    LastKind::Expr(expr) | LastKind::Then(expr) => expr.into_token_stream(),
+                    LastKind::Unit => quote! { #namespace::pure(()) },
+                };
+                let cont = quote! { { #(#lets;)* #result } };
+                match seg {
+                    Some(seg) => seg.and_then(namespace, counter, cont),
+                    None => cont,
+                }
+            }
+        };
+        for seg in segments {
+            acc = seg.and_then(namespace, counter, acc);
+        }
+        acc
+    }
+
+    /// Whether the plan gains nothing over plain monadic desugaring,
+    /// i.e. it needs `and_then` anyway, no group joins more than one effect
+    /// and the last expression is not sequenced with `then`.
+    fn is_sequential(&self) -> bool {
+        !self.binds.is_empty()
+            && self.segments.iter().all(|seg| seg.group.len() <= 1)
+            && !matches!(self.last.kind, LastKind::Then(_))
+    }
+}
+
+struct Last {
+    lets: Vec<TokenStream>,
+    kind: LastKind,
+}
+
+enum LastKind {
+    /// `return expr`, with the span of the statement.
+    Return(syn::Expr, Span),
+    Expr(syn::Expr),
+    /// An expression independent of the last group, sequenced after it with `NAMESPACE::then`.
+    Then(syn::Expr),
+    Unit,
+}
+
+/// Variables bound in the current group, or related to `let`s floated out of it.
+#[derive(Default)]
+struct GroupVars {
+    bound: HashSet<Ident>,
+    floated_bound: HashSet<Ident>,
+    floated_free: HashSet<Ident>,
+}
+
+impl GroupVars {
+    /// The first variable in `free` bound in the current group, if any.
+    fn dependency<'a>(&self, free: &'a [Ident]) -> Option<&'a Ident> {
+        free.iter()
+            .find(|v| self.bound.contains(v) || self.floated_bound.contains(v))
+    }
+
+    /// The first variable in `binders` whose binding would change the meaning of floated `let`s.
+    fn shadowed<'a>(&self, binders: &'a [Ident]) -> Option<&'a Ident> {
+        binders
+            .iter()
+            .find(|v| self.floated_bound.contains(v) || self.floated_free.contains(v))
+    }
+}
+
+/// A group of mutually independent effectful statements, preceded by `let`s it depends on.
+/// `let`s in the middle of a group are floated out to the beginning of the next segment.
+/// Only the last statement of a group can have a refutable pattern.
+#[derive(Default)]
+struct Segment {
+    lets: Vec<TokenStream>,
+    /// Effectful statements, with the patterns and spans.
+    group: Vec<(Pat, TokenStream, Span)>,
+    refutable: bool,
+    refutation: Refutation,
+    /// The span of the statement requiring `and_then` after the group, if any.
+    closed_by: Option<Span>,
+}
+
+impl Segment {
+    /// Closes the segment and starts a new one, preceded by the given `let`s.
+    fn close(&mut self, lets: Vec<TokenStream>) -> Segment {
+        std::mem::replace(
+            self,
+            Segment {
+                lets,
+                ..Segment::default()
+            },
+        )
+    }
+
+    /// Maps the joined group with a pure function,
+    /// spanned to the `return` statement if any, or to the last statement of the group.
+    /// The group MUST NOT end with a refutable pattern.
+    fn fmap(
+        self,
+        namespace: &Namespace,
+        counter: &mut u64,
+        span: Option<Span>,
+        bound: &HashSet<Ident>,
+        result: TokenStream,
+    ) -> TokenStream {
+        let lets = self.lets.clone();
+        let (body, pat, _, last) = self.join(namespace, counter);
+        let span = span.unwrap_or(last);
+        let fmap = namespace.method("fmap", span);
+        let capture = capture(bound, &pat, &result);
+        let body = quote_spanned! {span=> #fmap(#capture |#pat| #result, #body) };
+        quote! { { #(#lets;)* #body } }
+    }
+
+    /// Chains the joined group with the continuation by `and_then`,
+    /// spanned to the statement requiring it, or to the last statement of the group.
+    fn and_then(self, namespace: &Namespace, counter: &mut u64, cont: TokenStream) -> TokenStream {
+        let lets = self.lets.clone();
+        let refutation = self.refutation.clone();
+        let closed_by = self.closed_by;
+        let (body, pat, refutable, last) = self.join(namespace, counter);
+        let span = closed_by.unwrap_or(last);
+        let cont = mk_match_cont(namespace, refutable, refutation, cont);
+        let and_then = namespace.method("and_then", span);
+        let body = quote_spanned! {span=> #and_then(#body, move |#pat| #cont) };
+        quote! { { #(#lets;)* #body } }
+    }
+
+    /// Sequences the joined group and the last expression independent of it by `then`,
+    /// spanned to the last statement of the group.
+    fn then(self, namespace: &Namespace, counter: &mut u64, last: syn::Expr) -> TokenStream {
+        let lets = self.lets.clone();
+        let (body, _, _, span) = self.join(namespace, counter);
+        let then = namespace.method("then", span);
+        let body = quote_spanned! {span=> #then(#body, #last) };
+        quote! { { #(#lets;)* #body } }
+    }
+
+    /// Joins the group with `zip_with`, returning the joined expression,
+    /// the (nested) tuple pattern to bind its result, the refutable pattern to be matched against, if any,
+    /// and the span of the last statement.
+    /// Each `zip_with` and tuple is spanned to the statement joined there.
+    /// Type ascriptions on patterns are gathered into a (nested) tuple type of the closure parameter.
+    fn join(
+        self,
+        namespace: &Namespace,
+        counter: &mut u64,
+    ) -> (TokenStream, TokenStream, Option<(syn::Ident, Pat)>, Span) {
+        let Segment {
+            mut group,
+            refutable,
+            ..
+        } = self;
+        let mut sealer = PatVarSealer::default();
+        for (p, _, _) in group.iter_mut().rev() {
+            sealer.visit_pat_mut(p);
+        }
+        let mut group = group
+            .into_iter()
+            .map(|(p, e, span)| {
+                let (p, ty) = split_ascription(p);
+                (p, ty, e, span)
+            })
+            .collect::<Vec<_>>();
+        let refutable = if refutable {
+            let var = fresh_var(counter);
+            let (pat, _, _, _) = group.last_mut().unwrap();
+            let pat = std::mem::replace(pat, parse_quote! { #var });
+            Some((var, pat))
+        } else {
+            None
+        };
+        let typed = group.iter().any(|(_, ty, _, _)| ty.is_some());
+        let single = group.len() == 1;
+        let mut group = group.into_iter().map(|(p, ty, e, span)| {
+            let ty = ty.map_or_else(|| quote! { _ }, |ty| ty.into_token_stream());
+            (p, ty, e, span)
+        });
+        let (pat0, ty0, body0, span0) = group.next().unwrap();
+        let pat0 = if single {
+            top_level_pat(&pat0)
+        } else {
+            pat0.into_token_stream()
+        };
+        let (body, pat, ty, span) = group.fold(
+            (body0, pat0, ty0, span0),
+            |(body, pats, tys, _), (pat, ty, e, span)| {
+                let a = fresh_var(counter);
+                let b = fresh_var(counter);
+                let zip_with = namespace.method("zip_with", span);
+                (
+                    quote_spanned! {span=> #zip_with(|#a, #b| (#a, #b), #body, #e) },
+                    quote_spanned! {span=> (#pats, #pat) },
+                    quote_spanned! {span=> (#tys, #ty) },
+                    span,
+                )
+            },
+        );
+        let pat = if typed {
+            quote! { #pat: #ty }
+        } else {
+            pat
+        };
+        (body, pat, refutable, span)
+    }
+}
+
+/// What to do when a refutable pattern doesn't match.
+#[derive(Clone, Default)]
+struct Refutation {
+    /// The pattern guard, if any.
+    guard: Option<Box<syn::Expr>>,
+    /// The desugared `else` branch, if any.
+    fallback: Option<TokenStream>,
+    /// The source text of the bound expression, reported to `fail_with`.
+    scrutinee: String,
+}
+
+impl Refutation {
+    fn new(
+        guard: Option<(Token![if], Box<syn::Expr>)>,
+        else_branch: Option<(Token![else], DoBlock)>,
+        scrutinee: &DoExpr,
+        namespace: &Namespace,
+        mode: Mode,
+    ) -> Result<Refutation, Error> {
+        Ok(Refutation {
+            guard: guard.map(|(_, guard)| guard),
+            fallback: else_branch
+                .map(|(_, alt)| alt.lower(namespace, mode))
+                .transpose()?,
+            scrutinee: scrutinee.to_token_stream().to_string(),
+        })
+    }
+
+    fn is_trivial(&self) -> bool {
+        self.guard.is_none() && self.fallback.is_none()
+    }
+
+    /// The `else` branch, or `NAMESPACE::fail_with` reporting the binding if absent.
+    fn fallback(&self, namespace: &Namespace, p: &Pat) -> TokenStream {
+        self.fallback.clone().unwrap_or_else(|| {
+            let guard = self.guard.as_ref().map(|guard| quote! { if #guard });
+            let pattern = quote! { #p #guard }.to_string();
+            let scrutinee = &self.scrutinee;
+            // Spanned at the pattern so that `line!()` and `column!()` point at the binding.
+            let span = p.span();
+            let fail_with = namespace.method("fail_with", span);
+            quote_spanned! {span=>
+                #fail_with(::core::convert::Into::into((
+                    #pattern,
+                    #scrutinee,
+                    ::core::file!(),
+                    ::core::line!(),
+                    ::core::column!(),
+                )))
+            }
+        })
+    }
+}
+
+/// Matches the refutable pattern, falling back to the `else` branch or `NAMESPACE::fail_with` on mismatch.
+fn mk_match_cont(
+    namespace: &Namespace,
+    refutable: Option<(syn::Ident, Pat)>,
+    refutation: Refutation,
+    body: TokenStream,
+) -> TokenStream {
+    match refutable {
+        None => body,
+        Some((var, p)) => {
+            let fallback = refutation.fallback(namespace, &p);
+            let guard = refutation.guard.map(|guard| quote! { if #guard });
+            quote! {
+                match #var {
+                    #p #guard => #body,
+                    _ => #fallback,
+                }
+            }
+        }
+    }
+}
+
+/// Puts the pattern at the top level of closure parameters or `let`s, parenthesising or-patterns.
+fn top_level_pat(pat: &Pat) -> TokenStream {
+    match pat {
+        Pat::Or(_) => quote! { (#pat) },
+        Pat::Type(PatType { pat, ty, .. }) => {
+            let pat = top_level_pat(pat);
+            quote! { #pat: #ty }
+        }
+        pat => pat.into_token_stream(),
+    }
+}
+
+/// Splits the type ascription off the pattern, if any.
+fn split_ascription(pat: Pat) -> (Pat, Option<Box<syn::Type>>) {
+    match pat {
+        Pat::Type(PatType { pat, ty, .. }) => (*pat, Some(ty)),
+        pat => (pat, None),
+    }
+}
+
+/// Whether the pattern can be put directly in closure arguments.
+fn is_simple_pat(pat: &Pat) -> bool {
+    match pat {
+        Pat::Type(PatType { pat, .. }) => is_simple_pat(pat),
+        pat => matches!(
+            pat,
+            Pat::Ident(PatIdent { subpat: None, .. }) | Pat::Wild(_)
+        ),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PatVarSealer {
+    bound: HashSet<Ident>,
+}
+
+impl VisitMut for PatVarSealer {
+    fn visit_pat_mut(&mut self, node: &mut Pat) {
+        match node {
+            Pat::Ident(ident) => {
+                if self.bound.contains(&ident.ident) {
+                    *node = parse_quote! { _ };
+                } else {
+                    self.bound.insert(ident.ident.clone());
+                }
+            }
+            // Every alternative binds the same variables.
+            Pat::Or(or) => {
+                let before = self.bound.clone();
+                let mut after = before.clone();
+                for case in or.cases.iter_mut() {
+                    self.bound = before.clone();
+                    self.visit_pat_mut(case);
+                    after.extend(self.bound.drain());
+                }
+                self.bound = after;
+            }
+            p => syn::visit_mut::visit_pat_mut(self, p),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: TokenStream) -> Result<String, Error> {
+        Ok(syn::parse2::<QDo>(input)?.desugar()?.to_string())
+    }
+
+    #[test]
+    fn test_applicative_zip_with() {
+        let expanded = expand(quote! { Optioned {
+            x <- Some(1);
+            y <- Some(2);
+            return x + y
+        }});
+        let expected = quote! {
+            {
+                Optioned::fmap(
+                    |(x, y)| { x + y },
+                    Optioned::zip_with(|__qdo_arg_1, __qdo_arg_2| (__qdo_arg_1, __qdo_arg_2), Some(1), Some(2))
+                )
+            }
+        };
+        assert_eq!(expanded.unwrap(), expected.to_string());
+    }
+
+    #[test]
+    fn test_monadic_and_then() {
+        let expanded = expand(quote! { #[monadic] Optioned {
+            x <- Some(1);
+            y <- Some(x);
+            return y
+        }});
+        let expected = quote! {
+            Optioned::and_then(Some(1), move |x| Optioned::and_then(Some(x), move |y| Optioned::pure(y)))
+        };
+        assert_eq!(expanded.unwrap(), expected.to_string());
+    }
+
+    #[test]
+    fn test_last_expr_then() {
+        let expanded = expand(quote! { #[applicative] ZipIter {
+            x <- xs;
+            ys
+        }});
+        let expected = quote! {
+            { ZipIter::then(xs, ys) }
+        };
+        assert_eq!(expanded.unwrap(), expected.to_string());
+    }
+
+    #[test]
+    fn test_direct_branch() {
+        let expanded = expand(quote! { #[direct] Optioned {
+            x <- Some(1);
+            guard x > 0;
+            return x
+        }});
+        let expected = quote! {
+            '__qdo0: {
+                let x = match Optioned::branch(Some(1)) {
+                    ::core::ops::ControlFlow::Continue(v) => v,
+                    ::core::ops::ControlFlow::Break(r) => break '__qdo0 Optioned::from_residual(r),
+                };
+                let _ = match Optioned::branch(Optioned::guard(x > 0)) {
+                    ::core::ops::ControlFlow::Continue(v) => v,
+                    ::core::ops::ControlFlow::Break(r) => break '__qdo0 Optioned::from_residual(r),
+                };
+                Optioned::pure(x)
+            }
+        };
+        assert_eq!(expanded.unwrap(), expected.to_string());
+    }
+
+    #[test]
+    fn test_refutable_fail_with() {
+        let expanded = expand(quote! { Optioned {
+            x <- Some(1);
+            Some(y) <- f(x);
+            return y
+        }});
+        let pattern = quote! { Some(y) }.to_string();
+        let scrutinee = quote! { f(x) }.to_string();
+        let expected = quote! {
+            Optioned::and_then(Some(1), move |x| Optioned::and_then(f(x), move |__qdo_arg_1| match __qdo_arg_1 {
+                Some(y) => Optioned::pure(y),
+                _ => Optioned::fail_with(::core::convert::Into::into((
+                    #pattern,
+                    #scrutinee,
+                    ::core::file!(),
+                    ::core::line!(),
+                    ::core::column!(),
+                ))),
+            }))
+        };
+        assert_eq!(expanded.unwrap(), expected.to_string());
+    }
+
+    #[test]
+    fn test_strict_applicative_rejects_dependency() {
+        let err = expand(quote! { #[applicative] Optioned {
+            x <- Some(1);
+            y <- Some(x);
+            return y
+        }})
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`x` is bound by a preceding statement, so this statement requires `and_then`"
+        );
+    }
+
+    #[test]
+    fn test_stringify_mode() {
+        let qdo: QDo = parse_quote! { #[monadic] Optioned {
+            x <- Some(1);
+            return x
+        }};
+        assert_eq!(
+            qdo.stringify().unwrap(),
+            "// mode: monadic\nOptioned::and_then(Some(1), move |x| Optioned::pure(x))"
+        );
+    }
+
+    #[test]
+    fn test_stringify_long_lines() {
+        let qdo: QDo = parse_quote! { #[monadic] Optioned {
+            x <- Some(1);
+            y <- s.parse::<u32>().ok();
+            return x + y < LIMIT
+        }};
+        assert_eq!(
+            qdo.stringify().unwrap(),
+            [
+                "// mode: monadic",
+                "Optioned::and_then(",
+                "    Some(1),",
+                "    move |x| Optioned::and_then(",
+                "        s.parse::<u32>().ok(),",
+                "        move |y| Optioned::pure(x + y < LIMIT),",
+                "    ),",
+                ")",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_block() {
+        let err = expand(quote! { Optioned {} }).unwrap_err();
+        assert_eq!(err.to_string(), "expected at least one statement");
+    }
+
+    #[test]
+    fn test_then_take() {
+        let expanded = expand(quote! { #[monadic] UndetVec {
+            x <- xs;
+            then take 2;
+            return x
+        }});
+        let expected = quote! {
+            UndetVec::and_then(
+                UndetVec::take(2, UndetVec::and_then(xs, move |x| UndetVec::pure((x,)))),
+                move |__qdo_arg_1| {
+                    #[allow(unused_variables)]
+                    let (x,) = __qdo_arg_1;
+                    UndetVec::pure(x)
+                }
+            )
+        };
+        assert_eq!(expanded.unwrap(), expected.to_string());
+    }
+
+    #[test]
+    fn test_tell_statement() {
+        let expanded = expand(quote! { #[monadic] Written {
+            tell vec![1];
+            return 2
+        }});
+        let expected = quote! {
+            Written::and_then(Written::tell(vec![1]), |_| Written::pure(2))
+        };
+        assert_eq!(expanded.unwrap(), expected.to_string());
+    }
+
+    #[test]
+    fn test_discarded_statement_capture() {
+        // The continuation owns `x` bound in the block...
+        let expanded = expand(quote! { #[monadic] Stated::<u64> {
+            x <- Stated::<u64>::get();
+            Stated::<u64>::put(y);
+            return x
+        }});
+        let expected = quote! {
+            Stated::<u64>::and_then(Stated::<u64>::get(), move |x| Stated::<u64>::and_then(
+                Stated::<u64>::put(y),
+                move |_| Stated::<u64>::pure(x)
+            ))
+        };
+        assert_eq!(expanded.unwrap(), expected.to_string());
+
+        // ...but borrows `y` from outside the block.
+        let expanded = expand(quote! { #[monadic] Stated::<u64> {
+            x <- Stated::<u64>::get();
+            Stated::<u64>::put(x + 1);
+            return y
+        }});
+        let expected = quote! {
+            Stated::<u64>::and_then(Stated::<u64>::get(), move |x| Stated::<u64>::and_then(
+                Stated::<u64>::put(x + 1),
+                |_| Stated::<u64>::pure(y)
+            ))
+        };
+        assert_eq!(expanded.unwrap(), expected.to_string());
+    }
+
+    #[test]
+    fn test_qcomp_parallel_zip_with() {
+        let qcomp: QComp = parse_quote! { ZipVec; x + y | x <- xs | y <- ys, let z = y };
+        let expected = quote! {
+            ZipVec::fmap(
+                |__qdo_arg_3| {
+                    #[allow(unused_variables)]
+                    let ((x,), (y, z,)) = __qdo_arg_3;
+                    x + y
+                },
+                ZipVec::zip_with(
+                    |__qdo_arg_1, __qdo_arg_2| (__qdo_arg_1, __qdo_arg_2),
+                    { ZipVec::fmap(|x| { (x,) }, xs) },
+                    { ZipVec::fmap(|y| { let z = y; (y, z,) }, ys) }
+                )
+            )
+        };
+        assert_eq!(qcomp.desugar().unwrap().to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_qcomp_rejects_expression() {
+        let Err(err) = syn::parse2::<QComp>(quote! { Optioned; x | x <- xs, f(x) }) else {
+            panic!("a bare expression must not be accepted as a qualifier");
+        };
+        assert_eq!(
+            err.to_string(),
+            "expected a qualifier: `pat <- expr`, `let pat = expr`, `guard cond` or `then transform`"
+        );
+    }
+
+    #[test]
+    fn test_qdo_fn_markers() {
+        let qdo_fn: QDoFn = parse_quote! { #[monadic] Optioned };
+        let block: syn::Block = parse_quote! {{
+            let x: i64 = bind!(lookup("x"));
+            let 0..=9 = bind!(digit(x)) else { return 10 };
+            let y = x == 1;
+            guard!(x > 0);
+            if y {
+                return ()
+            } else {
+                throw!(())
+            };
+            return x
+        }};
+        let expected = quote! { #[monadic] Optioned {
+            x: i64 <- lookup("x");
+            0..=9 <- digit(x) else { return 10 };
+            let y = x == 1;
+            guard x > 0;
+            if y {
+                return ()
+            } else {
+                throw ()
+            };
+            return x
+        }};
+        assert_eq!(
+            qdo_fn
+                .parse_body(&block)
+                .unwrap()
+                .desugar()
+                .unwrap()
+                .to_string(),
+            expand(expected).unwrap()
+        );
+    }
+}
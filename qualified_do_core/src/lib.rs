@@ -0,0 +1,25 @@
+//! The AST, parser and desugarer of `qdo!`, `ado!` and `#[qdo_fn]`, working on [`proc_macro2::TokenStream`].
+//!
+//! This crate is not a procedural macro by itself, so other macros can embed do-notation in their own DSL,
+//! and expansions can be tested without compiling them.
+//!
+//! ```
+//! use qualified_do_core::QDo;
+//! use quote::quote;
+//!
+//! let qdo: QDo = syn::parse2(quote! { Optioned {
+//!     x <- Some(1);
+//!     y <- Some(2);
+//!     return x + y
+//! }})
+//! .unwrap();
+//! let expanded = qdo.desugar().unwrap();
+//! assert!(expanded.to_string().contains("zip_with"));
+//! ```
+
+pub mod desugar;
+pub mod parser;
+pub mod types;
+pub mod vars;
+
+pub use types::*;
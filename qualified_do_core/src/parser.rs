@@ -0,0 +1,656 @@
+use proc_macro2::{Delimiter, Group, Punct, Spacing, TokenStream, TokenTree};
+use punctuated::Punctuated;
+use syn::{parse::*, *};
+
+use super::types;
+use super::types::*;
+
+impl Parse for DoStatement {
+    fn parse(input: ParseStream) -> Result<Self> {
+        use DoStatement::*;
+        if input.peek(Token![return]) {
+            Ok(Return(input.parse()?))
+        } else if input.peek(Token![let]) {
+            Ok(Let(input.parse()?))
+        } else if input.peek(keywords::guard) {
+            Ok(Guard(input.parse()?))
+        } else if input.peek(keywords::then)
+            && (input.peek2(keywords::take)
+                || input.peek2(keywords::sort_by)
+                || input.peek2(keywords::group))
+        {
+            Ok(Then(input.parse()?))
+        } else if input.peek(Token![~]) {
+            Ok(Bind(input.parse()?))
+            // FIXME: This might be too expensive;
+            // consider using a more efficient way of parsing.
+        } else if input.peek(Token![if])
+            || input.peek(Token![match])
+            || input.peek(Token![for])
+            || input.peek(Token![try])
+            || input.peek(keywords::throw)
+            || input.peek(keywords::tell)
+        {
+            Ok(Expr(input.parse()?))
+        } else if input.fork().parse::<types::Bind>().is_ok() {
+            Ok(Bind(input.parse().unwrap()))
+        } else {
+            Ok(Expr(input.parse()?))
+        }
+    }
+}
+
+impl Parse for Return {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Return {
+            return_token: input.parse()?,
+            expr: input.parse::<Expr>()?,
+        })
+    }
+}
+
+impl Parse for Guard {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Guard {
+            guard_token: input.parse::<keywords::guard>()?,
+            cond: input.parse::<Expr>()?,
+        })
+    }
+}
+
+impl Parse for Then {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Then {
+            then_token: input.parse()?,
+            transform: input.parse()?,
+        })
+    }
+}
+
+impl Parse for Transform {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(keywords::take) {
+            Ok(Transform::Take(input.parse()?, input.parse()?))
+        } else if lookahead.peek(keywords::sort_by) {
+            Ok(Transform::SortBy(input.parse()?, input.parse()?))
+        } else if lookahead.peek(keywords::group) {
+            Ok(Transform::GroupBy {
+                group_token: input.parse()?,
+                by_token: input.parse()?,
+                key: input.parse()?,
+                using: if input.peek(keywords::using) {
+                    Some((input.parse()?, input.parse()?))
+                } else {
+                    None
+                },
+            })
+        } else {
+            Err(lookahead.error())
+        }
+    }
+}
+
+impl Parse for Let {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Let {
+            let_token: input.parse::<Token![let]>()?,
+            pat: Pat::parse_single(input)?,
+            eq_token: input.parse::<Token![=]>()?,
+            expr: input.parse::<Expr>()?,
+            else_branch: parse_else(input)?,
+        })
+    }
+}
+
+impl Parse for Bind {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let irrefutable = input.parse()?;
+        let mut pat = Pat::parse_multi_with_leading_vert(input)?;
+        if input.peek(Token![:]) {
+            pat = Pat::Type(PatType {
+                attrs: Vec::new(),
+                pat: Box::new(pat),
+                colon_token: input.parse()?,
+                ty: Box::new(parse_ascription(input)?),
+            });
+        }
+        Ok(Bind {
+            irrefutable,
+            pat,
+            bind_token: input.parse::<Token![<-]>()?,
+            body: input.parse()?,
+            guard: if input.peek(Token![if]) {
+                Some((input.parse()?, input.parse()?))
+            } else {
+                None
+            },
+            else_branch: parse_else(input)?,
+        })
+    }
+}
+
+/// Parses the type ascription of a binder, up to `<-`.
+///
+/// `Type`'s parser cannot be used directly, as it regards `<-` as the beginning of generic arguments.
+fn parse_ascription(input: ParseStream) -> Result<Type> {
+    let tokens = input.step(|cursor| {
+        let mut rest = *cursor;
+        let mut tokens = proc_macro2::TokenStream::new();
+        while let Some((tt, next)) = rest.token_tree() {
+            if let Some((lt, after)) = rest.punct() {
+                let is_arrow = after
+                    .punct()
+                    .is_some_and(|(minus, _)| minus.as_char() == '-');
+                if lt.as_char() == '<' && lt.spacing() == proc_macro2::Spacing::Joint && is_arrow {
+                    break;
+                }
+            }
+            tokens.extend([tt]);
+            rest = next;
+        }
+        Ok((tokens, rest))
+    })?;
+    parse2(tokens)
+}
+
+/// Parses an optional `else { stmts }` following a binding.
+fn parse_else(input: ParseStream) -> Result<Option<(Token![else], DoBlock)>> {
+    if input.peek(Token![else]) {
+        Ok(Some((input.parse()?, input.parse()?)))
+    } else {
+        Ok(None)
+    }
+}
+
+impl Parse for DoExpr {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.peek(Token![if]) {
+            Ok(DoExpr::If(input.parse()?))
+        } else if input.peek(Token![match]) {
+            Ok(DoExpr::Match(input.parse()?))
+        } else if input.peek(Token![for]) {
+            Ok(DoExpr::For(input.parse()?))
+        } else if input.peek(Token![try]) {
+            Ok(DoExpr::Try(input.parse()?))
+        } else if input.peek(keywords::throw) {
+            Ok(DoExpr::Throw(input.parse()?))
+        } else if input.peek(keywords::tell) {
+            Ok(DoExpr::Tell(input.parse()?))
+        } else {
+            Ok(DoExpr::Expr(input.parse()?))
+        }
+    }
+}
+
+impl Parse for DoBlock {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        let brace_token = braced!(content in input);
+        let statements = Punctuated::<DoStatement, Token![;]>::parse_terminated(&content)?;
+        if statements.is_empty() {
+            return Err(Error::new(
+                content.span(),
+                "expected at least one statement",
+            ));
+        }
+        let trailing_semi = statements.trailing_punct();
+        let statements = statements.into_iter().collect();
+        Ok(DoBlock {
+            brace_token,
+            statements,
+            trailing_semi,
+        })
+    }
+}
+
+impl Parse for DoIf {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let if_token = input.parse()?;
+        let cond = Box::new(Expr::parse_without_eager_brace(input)?);
+        let then_branch = input.parse()?;
+        let else_branch = if input.peek(Token![else]) {
+            let else_token = input.parse()?;
+            let else_branch = if input.peek(Token![if]) {
+                DoElse::If(Box::new(input.parse()?))
+            } else {
+                DoElse::Block(input.parse()?)
+            };
+            Some((else_token, else_branch))
+        } else {
+            None
+        };
+        Ok(DoIf {
+            if_token,
+            cond,
+            then_branch,
+            else_branch,
+        })
+    }
+}
+
+impl Parse for DoMatch {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let match_token = input.parse()?;
+        let expr = Box::new(Expr::parse_without_eager_brace(input)?);
+        let content;
+        let brace_token = braced!(content in input);
+        let mut arms = Vec::new();
+        while !content.is_empty() {
+            arms.push(content.parse()?);
+        }
+        Ok(DoMatch {
+            match_token,
+            expr,
+            brace_token,
+            arms,
+        })
+    }
+}
+
+impl Parse for DoFor {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(DoFor {
+            for_token: input.parse()?,
+            pat: Pat::parse_multi_with_leading_vert(input)?,
+            in_token: input.parse()?,
+            expr: Box::new(Expr::parse_without_eager_brace(input)?),
+            body: input.parse()?,
+        })
+    }
+}
+
+impl Parse for Throw {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Throw {
+            throw_token: input.parse()?,
+            expr: input.parse()?,
+        })
+    }
+}
+
+impl Parse for Tell {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Tell {
+            tell_token: input.parse()?,
+            expr: input.parse()?,
+        })
+    }
+}
+
+impl Parse for DoTry {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let try_token = input.parse()?;
+        let body = input.parse()?;
+        let mut catches = Vec::new();
+        while input.peek(keywords::catch) {
+            catches.push((input.parse()?, input.parse()?));
+        }
+        if catches.is_empty() {
+            return Err(input.error("expected `catch` after `try` block"));
+        }
+        Ok(DoTry {
+            try_token,
+            body,
+            catches,
+        })
+    }
+}
+
+impl Parse for DoArm {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let pat = Pat::parse_multi_with_leading_vert(input)?;
+        let guard = if input.peek(Token![if]) {
+            Some((input.parse()?, Box::new(input.parse()?)))
+        } else {
+            None
+        };
+        let fat_arrow_token = input.parse()?;
+        let body = if input.peek(token::Brace) {
+            input.parse()?
+        } else {
+            // A bare expression arm is a do-block consisting of the sole expression.
+            DoBlock {
+                brace_token: Default::default(),
+                statements: vec![DoStatement::Expr(DoExpr::Expr(input.parse()?))],
+                trailing_semi: false,
+            }
+        };
+        let comma = input.parse()?;
+        Ok(DoArm {
+            pat,
+            guard,
+            fat_arrow_token,
+            body,
+            comma,
+        })
+    }
+}
+
+impl Parse for Namespace {
+    fn parse(input: ParseStream) -> Result<Self> {
+        input.parse().map(Namespace)
+    }
+}
+
+fn parse_attrs(attrs: Vec<Attribute>) -> Result<(Mode, Option<Explain>)> {
+    let mut mode = None;
+    let mut explain = None;
+    for attr in attrs {
+        if attr.path().is_ident("explain") {
+            if explain.is_some() {
+                return Err(Error::new_spanned(
+                    attr,
+                    "`#[explain]` is specified more than once",
+                ));
+            }
+            explain = Some(parse_explain(&attr)?);
+            continue;
+        }
+        attr.meta.require_path_only()?;
+        let this = if attr.path().is_ident("monadic") {
+            Mode::Monadic
+        } else if attr.path().is_ident("applicative") {
+            Mode::Applicative
+        } else if attr.path().is_ident("direct") {
+            Mode::Direct
+        } else {
+            return Err(Error::new_spanned(
+                attr,
+                "unknown attribute; expected `#[monadic]`, `#[applicative]`, `#[direct]` or `#[explain]`",
+            ));
+        };
+        if mode.is_some() {
+            return Err(Error::new_spanned(
+                attr,
+                "desugaring mode is specified more than once",
+            ));
+        }
+        mode = Some(this);
+    }
+    Ok((mode.unwrap_or_default(), explain))
+}
+
+/// Parses `#[explain]` or `#[explain(strict)]`.
+fn parse_explain(attr: &Attribute) -> Result<Explain> {
+    match &attr.meta {
+        Meta::Path(_) => Ok(Explain::Warn),
+        Meta::List(list) => {
+            let arg: Ident = list.parse_args()?;
+            if arg == "strict" {
+                Ok(Explain::Strict)
+            } else {
+                Err(Error::new_spanned(arg, "expected `strict`"))
+            }
+        }
+        Meta::NameValue(_) => Err(Error::new_spanned(
+            attr,
+            "expected `#[explain]` or `#[explain(strict)]`",
+        )),
+    }
+}
+
+impl Parse for QDo {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let (mode, explain) = parse_attrs(input.call(Attribute::parse_outer)?)?;
+        let namespace = input.parse()?;
+        let DoBlock {
+            statements,
+            trailing_semi,
+            ..
+        } = input.parse()?;
+
+        Ok(QDo {
+            mode,
+            explain,
+            namespace,
+            statements,
+            trailing_semi,
+        })
+    }
+}
+
+impl Parse for QDoFn {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let (mode, explain) = parse_attrs(input.call(Attribute::parse_outer)?)?;
+        let namespace = input.parse()?;
+        Ok(QDoFn {
+            mode,
+            explain,
+            namespace,
+        })
+    }
+}
+
+impl QDoFn {
+    /// Parses the statements of a function body as a `qdo` block, after [`expand_markers`].
+    pub fn parse_body(self, block: &Block) -> Result<QDo> {
+        let stmts = &block.stmts;
+        let mut body = Group::new(Delimiter::Brace, expand_markers(quote::quote!(#(#stmts)*)));
+        body.set_span(block.brace_token.span.join());
+        let DoBlock {
+            statements,
+            trailing_semi,
+            ..
+        } = parse2(TokenTree::Group(body).into())?;
+        Ok(QDo {
+            mode: self.mode,
+            explain: self.explain,
+            namespace: self.namespace,
+            statements,
+            trailing_semi,
+        })
+    }
+}
+
+/// Rewrites the markers of a `#[qdo_fn]` body, which is valid Rust, into the syntax of `qdo!`:
+/// `let pat = bind!(expr)` into `pat <- expr`, and `guard!(cond)`, `throw!(err)` and `tell!(w)`
+/// into `guard cond`, `throw err` and `tell w`.
+///
+/// Markers are rewritten in nested braces as well, so that they can be used in the arms of `if`, `match` and `for`.
+pub fn expand_markers(tokens: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut out = TokenStream::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let stmt_start =
+            i == 0 || matches!(&tokens[i - 1], TokenTree::Punct(p) if p.as_char() == ';');
+        if stmt_start && matches!(&tokens[i], TokenTree::Ident(l) if l == "let") {
+            if let Some((eq, bind, args)) = let_bind(&tokens[i + 1..]) {
+                let mut arrow = [
+                    Punct::new('<', Spacing::Joint),
+                    Punct::new('-', Spacing::Alone),
+                ];
+                arrow.iter_mut().for_each(|p| p.set_span(bind.span()));
+                out.extend(tokens[i + 1..i + 1 + eq].iter().cloned());
+                out.extend(arrow.map(TokenTree::Punct));
+                out.extend(expand_markers(args.stream()));
+                i += eq + 5;
+                continue;
+            }
+        }
+        if let TokenTree::Ident(marker) = &tokens[i] {
+            if let Some(args) = ["guard", "throw", "tell"]
+                .iter()
+                .find_map(|name| macro_call(&tokens[i..], name))
+            {
+                out.extend([TokenTree::Ident(marker.clone())]);
+                out.extend(expand_markers(args.stream()));
+                i += 3;
+                continue;
+            }
+        }
+        match &tokens[i] {
+            TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
+                let mut group = Group::new(Delimiter::Brace, expand_markers(g.stream()));
+                group.set_span(g.span());
+                out.extend([TokenTree::Group(group)]);
+            }
+            tt => out.extend([tt.clone()]),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Finds `pat = bind!(expr)` after `let`, returning the index of `=`, `bind` and the arguments.
+///
+/// `bind!(expr)` must be the whole initialiser, followed by `;`, `else` or nothing.
+fn let_bind(tokens: &[TokenTree]) -> Option<(usize, &Ident, &Group)> {
+    let eq = tokens.iter().enumerate().position(|(k, tt)| match tt {
+        // Not a part of `==`, `<=`, `..=` and the like.
+        TokenTree::Punct(p) => {
+            p.as_char() == '='
+                && p.spacing() == Spacing::Alone
+                && !(k > 0
+                    && matches!(&tokens[k - 1], TokenTree::Punct(q) if q.spacing() == Spacing::Joint))
+        }
+        _ => false,
+    })?;
+    if tokens[..eq]
+        .iter()
+        .any(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == ';'))
+    {
+        return None;
+    }
+    let args = macro_call(&tokens[eq + 1..], "bind")?;
+    let Some(TokenTree::Ident(bind)) = tokens.get(eq + 1) else {
+        return None;
+    };
+    match tokens.get(eq + 4) {
+        None => {}
+        Some(TokenTree::Punct(p)) if p.as_char() == ';' => {}
+        Some(TokenTree::Ident(e)) if e == "else" => {}
+        Some(_) => return None,
+    }
+    Some((eq, bind, args))
+}
+
+/// The arguments of `name!(...)` at the beginning of the tokens.
+fn macro_call<'t>(tokens: &'t [TokenTree], name: &str) -> Option<&'t Group> {
+    match tokens {
+        [TokenTree::Ident(i), TokenTree::Punct(bang), TokenTree::Group(args), ..]
+            if i == name && bang.as_char() == '!' =>
+        {
+            Some(args)
+        }
+        _ => None,
+    }
+}
+
+impl Parse for QComp {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let (mode, explain) = parse_attrs(input.call(Attribute::parse_outer)?)?;
+        if explain.is_some() {
+            return Err(input.error("`#[explain]` is not supported in `qcomp!`"));
+        }
+        let namespace = input.parse()?;
+        input.parse::<Token![;]>()?;
+        let head = parse2(comp_chunk(input)?)?;
+        let mut branches = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![|]>()?;
+            let mut qualifiers = Vec::new();
+            loop {
+                let chunk = comp_chunk(input)?;
+                if chunk.is_empty() {
+                    return Err(input.error("expected a qualifier"));
+                }
+                qualifiers.push(parse_qualifier(chunk)?);
+                if !input.peek(Token![,]) {
+                    break;
+                }
+                input.parse::<Token![,]>()?;
+            }
+            branches.push(qualifiers);
+        }
+        if branches.is_empty() {
+            return Err(input.error("expected `|` followed by qualifiers"));
+        }
+        Ok(QComp {
+            mode,
+            namespace,
+            head,
+            branches,
+        })
+    }
+}
+
+/// Parses a qualifier of a comprehension: `pat <- expr`, `let pat = expr`, `guard cond` or `then transform`.
+fn parse_qualifier(tokens: proc_macro2::TokenStream) -> Result<DoStatement> {
+    let span = tokens.clone().into_iter().next().unwrap().span();
+    match parse2(tokens)? {
+        stmt @ (DoStatement::Bind(_)
+        | DoStatement::Let(_)
+        | DoStatement::Guard(_)
+        | DoStatement::Then(_)) => Ok(stmt),
+        _ => Err(Error::new(
+            span,
+            "expected a qualifier: `pat <- expr`, `let pat = expr`, `guard cond` or `then transform`",
+        )),
+    }
+}
+
+/// Collects tokens of the head or a qualifier of a comprehension, up to `,` or `|` at the top level.
+///
+/// Or-patterns and bitwise-or in comprehensions must be parenthesised, while `||` can be used as is.
+/// A `|` where an operand is expected (at the beginning, after an operator or `move`)
+/// starts a closure, whose parameters are collected up to the closing `|`.
+fn comp_chunk(input: ParseStream) -> Result<proc_macro2::TokenStream> {
+    input.step(|cursor| {
+        let mut rest = *cursor;
+        let mut tokens = proc_macro2::TokenStream::new();
+        let mut last: Option<proc_macro2::TokenTree> = None;
+        while let Some((tt, next)) = rest.token_tree() {
+            if let Some((p, after)) = rest.punct() {
+                match p.as_char() {
+                    ',' => break,
+                    '|' if p.spacing() == proc_macro2::Spacing::Joint
+                        && after.punct().is_some_and(|(q, _)| q.as_char() == '|') =>
+                    {
+                        let (q, after) = after.punct().unwrap();
+                        tokens.extend([
+                            proc_macro2::TokenTree::Punct(p.clone()),
+                            proc_macro2::TokenTree::Punct(q),
+                        ]);
+                        last = Some(proc_macro2::TokenTree::Punct(p));
+                        rest = after;
+                        continue;
+                    }
+                    '|' if starts_operand(last.as_ref()) => {
+                        tokens.extend([tt]);
+                        rest = next;
+                        // The closure parameters, up to the closing `|`.
+                        while let Some((tt, next)) = rest.token_tree() {
+                            let closing = matches!(&tt, proc_macro2::TokenTree::Punct(q) if q.as_char() == '|');
+                            tokens.extend([tt.clone()]);
+                            last = Some(tt);
+                            rest = next;
+                            if closing {
+                                break;
+                            }
+                        }
+                        continue;
+                    }
+                    '|' => break,
+                    _ => {}
+                }
+            }
+            tokens.extend([tt.clone()]);
+            last = Some(tt);
+            rest = next;
+        }
+        Ok((tokens, rest))
+    })
+}
+
+/// Whether an operand is expected after the token, i.e. a `|` there starts a closure rather than separating qualifiers.
+fn starts_operand(last: Option<&proc_macro2::TokenTree>) -> bool {
+    match last {
+        None => true,
+        // `?` and `>` (closing generics) end operands.
+        Some(proc_macro2::TokenTree::Punct(p)) => !matches!(p.as_char(), '?' | '>'),
+        Some(proc_macro2::TokenTree::Ident(i)) => i == "move",
+        Some(_) => false,
+    }
+}
@@ -0,0 +1,438 @@
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::{token, Expr, ExprPath, Pat, Token};
+
+#[derive(Clone)]
+pub enum DoStatement {
+    Return(Return),
+    Let(Let),
+    Bind(Bind),
+    Expr(DoExpr),
+    Guard(Guard),
+    Then(Then),
+}
+
+impl ToTokens for DoStatement {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            DoStatement::Return(r) => r.to_tokens(tokens),
+            DoStatement::Let(l) => l.to_tokens(tokens),
+            DoStatement::Bind(b) => b.to_tokens(tokens),
+            DoStatement::Expr(e) => e.to_tokens(tokens),
+            DoStatement::Guard(g) => g.to_tokens(tokens),
+            DoStatement::Then(t) => t.to_tokens(tokens),
+        }
+    }
+}
+
+/// An effectful expression, possibly a control flow whose arms are do-blocks.
+#[derive(Clone)]
+pub enum DoExpr {
+    Expr(Expr),
+    If(DoIf),
+    Match(DoMatch),
+    For(DoFor),
+    Throw(Throw),
+    Try(DoTry),
+    Tell(Tell),
+}
+
+impl ToTokens for DoExpr {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            DoExpr::Expr(e) => e.to_tokens(tokens),
+            DoExpr::If(i) => i.to_tokens(tokens),
+            DoExpr::Match(m) => m.to_tokens(tokens),
+            DoExpr::For(f) => f.to_tokens(tokens),
+            DoExpr::Throw(t) => t.to_tokens(tokens),
+            DoExpr::Try(t) => t.to_tokens(tokens),
+            DoExpr::Tell(t) => t.to_tokens(tokens),
+        }
+    }
+}
+
+/// Statements in braces, desugared with the namespace of the enclosing block.
+#[derive(Clone)]
+pub struct DoBlock {
+    pub brace_token: token::Brace,
+    pub statements: Vec<DoStatement>,
+    pub trailing_semi: bool,
+}
+
+impl ToTokens for DoBlock {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.brace_token.surround(tokens, |tokens| {
+            let n = self.statements.len();
+            for (i, stmt) in self.statements.iter().enumerate() {
+                stmt.to_tokens(tokens);
+                if i + 1 < n || self.trailing_semi {
+                    <Token![;]>::default().to_tokens(tokens);
+                }
+            }
+        });
+    }
+}
+
+/// `if cond { stmts } else { stmts }`.
+#[derive(Clone)]
+pub struct DoIf {
+    pub if_token: Token![if],
+    pub cond: Box<Expr>,
+    pub then_branch: DoBlock,
+    pub else_branch: Option<(Token![else], DoElse)>,
+}
+
+impl ToTokens for DoIf {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.if_token.to_tokens(tokens);
+        self.cond.to_tokens(tokens);
+        self.then_branch.to_tokens(tokens);
+        if let Some((else_token, else_branch)) = &self.else_branch {
+            else_token.to_tokens(tokens);
+            else_branch.to_tokens(tokens);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum DoElse {
+    If(Box<DoIf>),
+    Block(DoBlock),
+}
+
+impl ToTokens for DoElse {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            DoElse::If(i) => i.to_tokens(tokens),
+            DoElse::Block(b) => b.to_tokens(tokens),
+        }
+    }
+}
+
+/// `match expr { pat => { stmts }, ... }`.
+#[derive(Clone)]
+pub struct DoMatch {
+    pub match_token: Token![match],
+    pub expr: Box<Expr>,
+    pub brace_token: token::Brace,
+    pub arms: Vec<DoArm>,
+}
+
+impl ToTokens for DoMatch {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.match_token.to_tokens(tokens);
+        self.expr.to_tokens(tokens);
+        self.brace_token.surround(tokens, |tokens| {
+            for arm in &self.arms {
+                arm.to_tokens(tokens);
+            }
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct DoArm {
+    pub pat: Pat,
+    pub guard: Option<(Token![if], Box<Expr>)>,
+    pub fat_arrow_token: Token![=>],
+    pub body: DoBlock,
+    pub comma: Option<Token![,]>,
+}
+
+impl ToTokens for DoArm {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.pat.to_tokens(tokens);
+        if let Some((if_token, guard)) = &self.guard {
+            if_token.to_tokens(tokens);
+            guard.to_tokens(tokens);
+        }
+        self.fat_arrow_token.to_tokens(tokens);
+        self.body.to_tokens(tokens);
+        self.comma.to_tokens(tokens);
+    }
+}
+
+/// `for pat in expr { stmts }`.
+#[derive(Clone)]
+pub struct DoFor {
+    pub for_token: Token![for],
+    pub pat: Pat,
+    pub in_token: Token![in],
+    pub expr: Box<Expr>,
+    pub body: DoBlock,
+}
+
+impl ToTokens for DoFor {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.for_token.to_tokens(tokens);
+        self.pat.to_tokens(tokens);
+        self.in_token.to_tokens(tokens);
+        self.expr.to_tokens(tokens);
+        self.body.to_tokens(tokens);
+    }
+}
+
+/// `throw expr`.
+#[derive(Clone)]
+pub struct Throw {
+    pub throw_token: keywords::throw,
+    pub expr: Box<Expr>,
+}
+
+impl ToTokens for Throw {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.throw_token.to_tokens(tokens);
+        self.expr.to_tokens(tokens);
+    }
+}
+
+/// `tell expr`.
+#[derive(Clone)]
+pub struct Tell {
+    pub tell_token: keywords::tell,
+    pub expr: Box<Expr>,
+}
+
+impl ToTokens for Tell {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.tell_token.to_tokens(tokens);
+        self.expr.to_tokens(tokens);
+    }
+}
+
+/// `try { stmts } catch pat => { stmts } ...`.
+#[derive(Clone)]
+pub struct DoTry {
+    pub try_token: Token![try],
+    pub body: DoBlock,
+    pub catches: Vec<(keywords::catch, DoArm)>,
+}
+
+impl ToTokens for DoTry {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.try_token.to_tokens(tokens);
+        self.body.to_tokens(tokens);
+        for (catch_token, arm) in &self.catches {
+            catch_token.to_tokens(tokens);
+            arm.to_tokens(tokens);
+        }
+    }
+}
+
+pub mod keywords {
+    syn::custom_keyword!(guard);
+    syn::custom_keyword!(throw);
+    syn::custom_keyword!(catch);
+    syn::custom_keyword!(tell);
+    syn::custom_keyword!(then);
+    syn::custom_keyword!(take);
+    syn::custom_keyword!(sort_by);
+    syn::custom_keyword!(group);
+    syn::custom_keyword!(by);
+    syn::custom_keyword!(using);
+}
+
+#[derive(Clone)]
+pub struct Guard {
+    pub guard_token: keywords::guard,
+    pub cond: Expr,
+}
+
+impl ToTokens for Guard {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.guard_token.to_tokens(tokens);
+        self.cond.to_tokens(tokens);
+    }
+}
+
+/// `then transform`: transforms the results of all the preceding statements as a whole.
+#[derive(Clone)]
+pub struct Then {
+    pub then_token: keywords::then,
+    pub transform: Transform,
+}
+
+impl ToTokens for Then {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.then_token.to_tokens(tokens);
+        self.transform.to_tokens(tokens);
+    }
+}
+
+#[derive(Clone)]
+pub enum Transform {
+    /// `take n`, desugared into `NAMESPACE::take`.
+    Take(keywords::take, Expr),
+    /// `sort_by key`, desugared into `NAMESPACE::sort_by`.
+    SortBy(keywords::sort_by, Expr),
+    /// `group by key using f`, desugared into `NAMESPACE::group_by` if `using f` is omitted.
+    /// Variables bound so far are rebound to `Vec`s of their values in each group.
+    GroupBy {
+        group_token: keywords::group,
+        by_token: keywords::by,
+        key: Expr,
+        using: Option<(keywords::using, Expr)>,
+    },
+}
+
+impl ToTokens for Transform {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Transform::Take(take_token, n) => {
+                take_token.to_tokens(tokens);
+                n.to_tokens(tokens);
+            }
+            Transform::SortBy(sort_by_token, key) => {
+                sort_by_token.to_tokens(tokens);
+                key.to_tokens(tokens);
+            }
+            Transform::GroupBy {
+                group_token,
+                by_token,
+                key,
+                using,
+            } => {
+                group_token.to_tokens(tokens);
+                by_token.to_tokens(tokens);
+                key.to_tokens(tokens);
+                if let Some((using_token, f)) = using {
+                    using_token.to_tokens(tokens);
+                    f.to_tokens(tokens);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Return {
+    pub return_token: Token![return],
+    pub expr: Expr,
+}
+
+impl ToTokens for Return {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.return_token.to_tokens(tokens);
+        self.expr.to_tokens(tokens);
+    }
+}
+
+#[derive(Clone)]
+pub struct Let {
+    pub let_token: Token![let],
+    pub pat: Pat,
+    pub eq_token: Token![=],
+    pub expr: Expr,
+    pub else_branch: Option<(Token![else], DoBlock)>,
+}
+
+impl ToTokens for Let {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.let_token.to_tokens(tokens);
+        self.pat.to_tokens(tokens);
+        self.eq_token.to_tokens(tokens);
+        self.expr.to_tokens(tokens);
+        if let Some((else_token, else_branch)) = &self.else_branch {
+            else_token.to_tokens(tokens);
+            else_branch.to_tokens(tokens);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Bind {
+    pub irrefutable: Option<Token![~]>,
+    pub pat: Pat,
+    pub bind_token: Token![<-],
+    pub body: DoExpr,
+    pub guard: Option<(Token![if], Box<Expr>)>,
+    pub else_branch: Option<(Token![else], DoBlock)>,
+}
+
+impl ToTokens for Bind {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.irrefutable.to_tokens(tokens);
+        self.pat.to_tokens(tokens);
+        self.bind_token.to_tokens(tokens);
+        self.body.to_tokens(tokens);
+        if let Some((if_token, guard)) = &self.guard {
+            if_token.to_tokens(tokens);
+            guard.to_tokens(tokens);
+        }
+        if let Some((else_token, else_branch)) = &self.else_branch {
+            else_token.to_tokens(tokens);
+            else_branch.to_tokens(tokens);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Namespace(pub ExprPath);
+
+impl ToTokens for Namespace {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.0.to_tokens(tokens);
+    }
+}
+
+/// Desugaring strategy of a `qdo` block, specified by an attribute.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// Splits statements into independent groups where possible (default).
+    #[default]
+    Auto,
+    /// `#[monadic]`: chains every statement with `and_then`.
+    Monadic,
+    /// `#[applicative]`: rejects statements requiring `and_then`.
+    Applicative,
+    /// `#[direct]`: evaluates statements in a labeled block, breaking early like `?`.
+    Direct,
+}
+
+impl Mode {
+    /// The name of the mode, as in the attribute.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Auto => "auto",
+            Mode::Monadic => "monadic",
+            Mode::Applicative => "applicative",
+            Mode::Direct => "direct",
+        }
+    }
+}
+
+/// Diagnostics on the desugaring, requested by `#[explain]`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Explain {
+    /// `#[explain]`: reports statements requiring `and_then` and questionable constructs as warnings.
+    Warn,
+    /// `#[explain(strict)]`: rejects the block with the first of them.
+    Strict,
+}
+
+#[derive(Clone)]
+pub struct QDo {
+    pub mode: Mode,
+    pub explain: Option<Explain>,
+    pub namespace: Namespace,
+    pub statements: Vec<DoStatement>,
+    pub trailing_semi: bool,
+}
+
+/// A monad comprehension, `qcomp![NAMESPACE; head | qualifiers | ...]`.
+#[derive(Clone)]
+pub struct QComp {
+    pub mode: Mode,
+    pub namespace: Namespace,
+    pub head: Expr,
+    /// Parallel branches of qualifiers, i.e. bindings, `let`s and guards.
+    pub branches: Vec<Vec<DoStatement>>,
+}
+
+/// The arguments of `#[qdo_fn(NAMESPACE)]`, which desugars a function body as a `qdo` block.
+#[derive(Clone)]
+pub struct QDoFn {
+    pub mode: Mode,
+    pub explain: Option<Explain>,
+    pub namespace: Namespace,
+}
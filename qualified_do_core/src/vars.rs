@@ -0,0 +1,295 @@
+//! Scope-aware analysis of variables in statements.
+
+use proc_macro2::{Delimiter, Ident, Literal, TokenStream, TokenTree};
+use std::collections::HashSet;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprPath, Pat, PatIdent};
+
+/// Collects variables bound by patterns, in order of appearance.
+#[derive(Default, Clone)]
+pub struct PatVarWalker {
+    pub pat_idents: Vec<Ident>,
+}
+
+impl PatVarWalker {
+    /// Variables bound by the pattern.
+    pub fn vars(pat: &Pat) -> Vec<Ident> {
+        let mut walker = PatVarWalker::default();
+        walker.visit_pat(pat);
+        walker.pat_idents
+    }
+}
+
+impl Visit<'_> for PatVarWalker {
+    fn visit_pat_ident(&mut self, node: &PatIdent) {
+        if !self.pat_idents.contains(&node.ident) {
+            self.pat_idents.push(node.ident.clone());
+        }
+        visit::visit_pat_ident(self, node);
+    }
+}
+
+/// Collects free variables in expressions, in order of their first occurrence.
+///
+/// Variables bound by closures, `let`s in blocks, `match` arms, `for` loops and `if let`/`while let`
+/// are bound only in their scopes.
+/// As macro invocations cannot be analysed precisely, all identifiers in their arguments
+/// (including inline arguments in format strings) are regarded as free, unless bound.
+#[derive(Default, Clone)]
+pub struct ExprVarWalker {
+    pub free: Vec<Ident>,
+    bound: HashSet<Ident>,
+}
+
+impl ExprVarWalker {
+    /// Free variables of the expression.
+    pub fn free_vars(expr: &Expr) -> Vec<Ident> {
+        let mut walker = ExprVarWalker::default();
+        walker.visit_expr(expr);
+        walker.free
+    }
+
+    fn add_free(&mut self, v: &Ident) {
+        if !self.bound.contains(v) && !self.free.contains(v) {
+            self.free.push(v.clone());
+        }
+    }
+
+    fn bind(&mut self, pat: &Pat) {
+        self.bound.extend(PatVarWalker::vars(pat));
+    }
+
+    /// Runs `f` in a nested scope, so that variables bound in `f` are forgotten afterwards.
+    fn scoped<F: FnOnce(&mut Self)>(&mut self, f: F) {
+        let saved = self.bound.clone();
+        f(self);
+        self.bound = saved;
+    }
+
+    fn visit_tokens(&mut self, tokens: TokenStream) {
+        let tokens = tokens.into_iter().collect::<Vec<_>>();
+        for (i, tt) in tokens.iter().enumerate() {
+            match tt {
+                TokenTree::Ident(v) => {
+                    let is_punct = |j: Option<usize>, c: char| {
+                        j.and_then(|j| tokens.get(j))
+                            .is_some_and(|t| matches!(t, TokenTree::Punct(p) if p.as_char() == c))
+                    };
+                    // Skips fields, methods, path segments and macro names.
+                    if !is_punct(i.checked_sub(1), '.')
+                        && !is_punct(i.checked_sub(1), ':')
+                        && !is_punct(Some(i + 1), ':')
+                        && !is_punct(Some(i + 1), '!')
+                    {
+                        self.add_free(v);
+                    }
+                }
+                TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
+                    // Braces may introduce `let`s.
+                    self.scoped(|this| this.visit_tokens(g.stream()));
+                }
+                TokenTree::Group(g) => self.visit_tokens(g.stream()),
+                TokenTree::Literal(lit) => self.visit_format_string(lit),
+                TokenTree::Punct(_) => {}
+            }
+        }
+    }
+
+    /// Collects inline arguments in format strings, such as `x` in `"{x:?}"`.
+    fn visit_format_string(&mut self, lit: &Literal) {
+        let Ok(syn::Lit::Str(lit)) = syn::parse_str::<syn::Lit>(&lit.to_string()) else {
+            return;
+        };
+        let s = lit.value();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '{' {
+                continue;
+            }
+            if chars.peek() == Some(&'{') {
+                chars.next();
+                continue;
+            }
+            let name = chars
+                .by_ref()
+                .take_while(|c| *c != '}' && *c != ':')
+                .collect::<String>();
+            if let Ok(v) = syn::parse_str::<Ident>(name.trim()) {
+                self.add_free(&Ident::new(&v.to_string(), lit.span()));
+            }
+        }
+    }
+}
+
+impl Visit<'_> for ExprVarWalker {
+    fn visit_expr_path(&mut self, node: &ExprPath) {
+        if let Some(v) = node.path.get_ident() {
+            self.add_free(v);
+        }
+    }
+
+    fn visit_expr_closure(&mut self, cls: &syn::ExprClosure) {
+        self.scoped(|this| {
+            for i in cls.inputs.iter() {
+                this.bind(i);
+            }
+            this.visit_expr(&cls.body);
+        });
+    }
+
+    fn visit_block(&mut self, block: &syn::Block) {
+        self.scoped(|this| visit::visit_block(this, block));
+    }
+
+    fn visit_local(&mut self, local: &syn::Local) {
+        if let Some(init) = &local.init {
+            self.visit_expr(&init.expr);
+            if let Some((_, diverge)) = &init.diverge {
+                self.visit_expr(diverge);
+            }
+        }
+        self.bind(&local.pat);
+    }
+
+    fn visit_arm(&mut self, arm: &syn::Arm) {
+        self.scoped(|this| {
+            this.bind(&arm.pat);
+            if let Some((_, guard)) = &arm.guard {
+                this.visit_expr(guard);
+            }
+            this.visit_expr(&arm.body);
+        });
+    }
+
+    fn visit_expr_for_loop(&mut self, node: &syn::ExprForLoop) {
+        self.visit_expr(&node.expr);
+        self.scoped(|this| {
+            this.bind(&node.pat);
+            this.visit_block(&node.body);
+        });
+    }
+
+    fn visit_expr_if(&mut self, node: &syn::ExprIf) {
+        // Variables bound by `if let` are visible only in the `then` branch.
+        self.scoped(|this| {
+            this.visit_expr(&node.cond);
+            this.visit_block(&node.then_branch);
+        });
+        if let Some((_, els)) = &node.else_branch {
+            self.visit_expr(els);
+        }
+    }
+
+    fn visit_expr_while(&mut self, node: &syn::ExprWhile) {
+        self.scoped(|this| {
+            this.visit_expr(&node.cond);
+            this.visit_block(&node.body);
+        });
+    }
+
+    fn visit_expr_let(&mut self, node: &syn::ExprLet) {
+        self.visit_expr(&node.expr);
+        self.bind(&node.pat);
+    }
+
+    fn visit_macro(&mut self, mac: &syn::Macro) {
+        self.visit_tokens(mac.tokens.clone());
+    }
+
+    fn visit_item(&mut self, _: &syn::Item) {
+        // Items cannot refer to local variables.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn free(expr: Expr) -> Vec<String> {
+        ExprVarWalker::free_vars(&expr)
+            .iter()
+            .map(Ident::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_pat_vars() {
+        let pat: Pat = parse_quote! { (a, Some(b @ 1..), Point { x, y: c }, ref mut d, ..) };
+        let vars = PatVarWalker::vars(&pat)
+            .iter()
+            .map(Ident::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(vars, ["a", "b", "x", "c", "d"]);
+    }
+
+    #[test]
+    fn test_for_pattern() {
+        assert_eq!(
+            free(parse_quote! { for (i, x) in xs.iter().enumerate() { total += i * x + y; } }),
+            ["xs", "total", "y"]
+        );
+        // The loop variable is not visible after the loop.
+        assert_eq!(free(parse_quote! { { for x in xs {} x } }), ["xs", "x"]);
+    }
+
+    #[test]
+    fn test_if_let_scope() {
+        assert_eq!(
+            free(parse_quote! { if let Some(x) = opt { x + a } else { x + b } }),
+            ["opt", "a", "x", "b"]
+        );
+        assert_eq!(
+            free(parse_quote! { if let Some(x) = opt && x > 0 { x } else { 0 } }),
+            ["opt"]
+        );
+    }
+
+    #[test]
+    fn test_while_let_scope() {
+        assert_eq!(
+            free(parse_quote! { { while let Some(x) = stack.pop() { f(x); } x } }),
+            ["stack", "f", "x"]
+        );
+    }
+
+    #[test]
+    fn test_let_shadowing() {
+        // The initialiser refers to the outer `x`, shadowed only afterwards.
+        assert_eq!(free(parse_quote! { { let x = x + 1; x * y } }), ["x", "y"]);
+        assert_eq!(
+            free(parse_quote! { { let y = 1; y } }),
+            Vec::<String>::new()
+        );
+        // `let`s in a block are not visible after it.
+        assert_eq!(free(parse_quote! { ({ let z = 1; z }, z) }), ["z"]);
+        assert_eq!(
+            free(parse_quote! { { let Some(v) = w else { return u; }; v } }),
+            ["w", "u"]
+        );
+    }
+
+    #[test]
+    fn test_closure_and_match_scope() {
+        assert_eq!(free(parse_quote! { |a| a + b }), ["b"]);
+        assert_eq!(
+            free(parse_quote! { match m { Some(k) if k > lim => k, _ => k } }),
+            ["m", "lim", "k"]
+        );
+    }
+
+    #[test]
+    fn test_macro_tokens() {
+        // Fields, methods, path segments and macro names are not variables.
+        assert_eq!(
+            free(parse_quote! { vec![p.field, q.method(), std::mem::take(r), inner!(s)] }),
+            ["p", "q", "r", "s"]
+        );
+        assert_eq!(
+            free(parse_quote! { println!("{a} {b:?} {{c}}", d) }),
+            ["a", "b", "d"]
+        );
+        // Variables bound outside the macro are not free.
+        assert_eq!(free(parse_quote! { |t| m!(t, u) }), ["u"]);
+    }
+}
@@ -1,12 +1,9 @@
-use proc_macro::TokenStream;
+use proc_macro::{Literal, TokenStream, TokenTree};
 
-mod desugar;
-mod parser;
-mod types;
+use qualified_do_core::*;
 
-use types::*;
-
-use syn::parse_macro_input;
+use quote::ToTokens;
+use syn::{parse_macro_input, Expr, ImplItemFn, Stmt};
 
 #[proc_macro]
 pub fn qdo(input: TokenStream) -> TokenStream {
@@ -16,3 +13,79 @@ pub fn qdo(input: TokenStream) -> TokenStream {
         |a| a.into(),
     )
 }
+
+/// Desugars the body of a function or an `impl` method as a `qdo` block, `#[qdo_fn(NAMESPACE)]`.
+///
+/// As the body must be valid Rust, bindings are written `let pat = bind!(expr);` instead of `pat <- expr;`,
+/// and `guard!(cond)`, `throw!(err)` and `tell!(w)` stand for `guard cond`, `throw err` and `tell w`.
+/// Mode attributes go before the namespace, e.g. `#[qdo_fn(#[monadic] Optioned)]`.
+#[proc_macro_attribute]
+pub fn qdo_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let qdo_fn: QDoFn = parse_macro_input!(attr as QDoFn);
+    let mut item: ImplItemFn = parse_macro_input!(item as ImplItemFn);
+    qdo_fn
+        .parse_body(&item.block)
+        .and_then(QDo::desugar)
+        .map_or_else(
+            |err: syn::Error| TokenStream::from(err.into_compile_error()),
+            |body| {
+                item.block.stmts = vec![Stmt::Expr(Expr::Verbatim(body), None)];
+                item.into_token_stream().into()
+            },
+        )
+}
+
+/// Monad comprehension, `qcomp![NAMESPACE; head | qualifiers]`.
+///
+/// Qualifiers are bindings `pat <- expr`, `let pat = expr`, `guard cond` and `then` statements, separated by `,`,
+/// and the comprehension is desugared as `qdo! {NAMESPACE { qualifiers; return head }}`.
+/// Parallel branches `| qualifiers | qualifiers` are joined with `NAMESPACE::zip_with`.
+#[proc_macro]
+pub fn qcomp(input: TokenStream) -> TokenStream {
+    let qcomp: QComp = parse_macro_input!(input as QComp);
+    qcomp.desugar().map_or_else(
+        |err: syn::Error| TokenStream::from(err.into_compile_error()),
+        |a| a.into(),
+    )
+}
+
+/// Expands to a `&'static str` holding the pretty-printed desugaring of the `qdo!` block,
+/// headed by a comment on the chosen strategy.
+///
+/// The block itself is not evaluated, so this is useful to see and test what `qdo!` generates.
+#[proc_macro]
+pub fn qdo_stringify(input: TokenStream) -> TokenStream {
+    let qdo: QDo = parse_macro_input!(input as QDo);
+    qdo.stringify().map_or_else(
+        |err: syn::Error| TokenStream::from(err.into_compile_error()),
+        |s| TokenStream::from(TokenTree::Literal(Literal::string(&s))),
+    )
+}
+
+/// `ApplicativeDo`-only variant of [`qdo!`].
+///
+/// The block is desugared only with `NAMESPACE::fmap`, `NAMESPACE::zip_with` and `NAMESPACE::guard`.
+/// Any statement which requires `NAMESPACE::and_then` is rejected at compile time.
+#[proc_macro]
+pub fn ado(input: TokenStream) -> TokenStream {
+    let mut qdo: QDo = parse_macro_input!(input as QDo);
+    let attr = match qdo.mode {
+        Mode::Monadic => Some("#[monadic]"),
+        Mode::Direct => Some("#[direct]"),
+        Mode::Auto | Mode::Applicative => None,
+    };
+    if let Some(attr) = attr {
+        return TokenStream::from(
+            syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!("`ado!` cannot be used with `{attr}`"),
+            )
+            .into_compile_error(),
+        );
+    }
+    qdo.mode = Mode::Applicative;
+    qdo.desugar().map_or_else(
+        |err: syn::Error| TokenStream::from(err.into_compile_error()),
+        |a| a.into(),
+    )
+}
@@ -0,0 +1,10 @@
+fn main() {
+    use qualified_do_macro::ado;
+
+    ado! {ZipVec {
+        x <- vec![1,2,3];
+        y <- vec![4,5,6];
+        if x % 2 == 1 { vec![()]} else { vec![] };
+        return x + y;
+    }}
+}
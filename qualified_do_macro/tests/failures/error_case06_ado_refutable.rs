@@ -0,0 +1,9 @@
+fn main() {
+    use qualified_do_macro::ado;
+
+    ado! {ZipVec {
+        x <- vec![1,2,3];
+        Some(y) <- vec![Some(4), None, Some(6)];
+        return x + y
+    }};
+}
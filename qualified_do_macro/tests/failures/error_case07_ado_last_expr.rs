@@ -0,0 +1,8 @@
+fn main() {
+    use qualified_do_macro::ado;
+
+    ado! {ZipVec {
+        x <- vec![1,2,3];
+        vec![x]
+    }};
+}
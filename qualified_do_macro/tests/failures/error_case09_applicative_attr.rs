@@ -0,0 +1,9 @@
+fn main() {
+    use qualified_do_macro::qdo;
+
+    let _: Option<i64> = qdo! {#[applicative] OptionFunctor {
+        x <- Some(1);
+        y <- Some(x + 1);
+        return y
+    }};
+}
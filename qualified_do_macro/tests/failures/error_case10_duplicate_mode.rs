@@ -0,0 +1,8 @@
+fn main() {
+    use qualified_do_macro::qdo;
+
+    let _: Option<i64> = qdo! {#[monadic] #[applicative] OptionFunctor {
+        x <- Some(1);
+        return x
+    }};
+}
@@ -0,0 +1,11 @@
+fn main() {
+    use functo_rs::control::*;
+    type Optioned = AsControl<OptionFunctor>;
+    use qualified_do_macro::ado;
+
+    ado! {Optioned {
+        x <- Some(1);
+        Some(y) <- Some(None::<i64>) else { return 0 };
+        return x + y
+    }};
+}
@@ -0,0 +1,11 @@
+fn main() {
+    use functo_rs::control::*;
+    use qualified_do_macro::qdo;
+
+    let _: i64 = qdo! {Identity {
+        x <- 1;
+        y <- x + 1;
+        guard y > x;
+        return x + y
+    }};
+}
@@ -0,0 +1,10 @@
+fn main() {
+    use qualified_do_macro::qdo;
+
+    let _: Option<i64> = qdo! {#[explain(strict)] OptionFunctor {
+        x <- Some(1);
+        y <- Some(2);
+        z <- Some(x + y);
+        return z
+    }};
+}
@@ -0,0 +1,14 @@
+#![deny(warnings)]
+
+fn main() {
+    use functo_rs::control::*;
+    use qualified_do_macro::qdo;
+
+    let _: Option<i64> = qdo! {#[explain] OptionFunctor {
+        x <- Some(1);
+        return x;
+        Some(y) <- Some(Some(2));
+        z <- Some(x + y);
+        Some(z)
+    }};
+}
@@ -0,0 +1,5 @@
+fn main() {
+    use qualified_do_macro::qcomp;
+
+    let _: Option<i64> = qcomp![Optioned; x | x <- Some(1), x > 0];
+}
@@ -0,0 +1,20 @@
+fn main() {
+    use qualified_do_macro::{ado, qdo};
+
+    let _: Vec<i32> = ado! {UndetVec {
+        x <- vec![3, 1, 2];
+        then sort_by x;
+        return x
+    }};
+
+    let _: Option<i32> = qdo! {#[direct] OptionFunctor {
+        x <- Some(1);
+        then take 1;
+        return x
+    }};
+
+    let _: Vec<i32> = qdo! {UndetVec {
+        x <- vec![3, 1, 2];
+        then take 1
+    }};
+}
@@ -0,0 +1,14 @@
+use functo_rs::control::AsControl;
+use functo_rs::impls::Validation;
+
+type Validated<E> = AsControl<Validation<E>>;
+
+fn main() {
+    use qualified_do_macro::qdo;
+
+    let _: Result<u32, Vec<String>> = qdo! {Validated::<Vec<String>> {
+        x <- Ok::<u32, Vec<String>>(1);
+        y <- if x > 0 { Ok(x) } else { Err(vec!["negative".to_string()]) };
+        return y
+    }};
+}
@@ -0,0 +1,14 @@
+use functo_rs::control::*;
+use qualified_do_macro::qdo_fn;
+type Optioned = AsControl<OptionFunctor>;
+
+#[qdo_fn(Optioned)]
+fn parse(s: Option<&str>) -> Option<i64> {
+    let s = bind!(s);
+    let n = bind!(s.parse::<i64>());
+    return n
+}
+
+fn main() {
+    let _ = parse(Some("1"));
+}
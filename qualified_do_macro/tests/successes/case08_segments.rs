@@ -0,0 +1,89 @@
+// A namespace recording which combinators are used.
+mod traced {
+    pub enum Traced {}
+
+    impl Traced {
+        pub fn fmap<A, B, F: FnOnce(A) -> B>(f: F, (a, log): (A, String)) -> (B, String) {
+            (f(a), log + "fmap;")
+        }
+
+        pub fn pure<A>(a: A) -> (A, String) {
+            (a, String::new())
+        }
+
+        pub fn zip_with<A, B, C, F: FnOnce(A, B) -> C>(
+            f: F,
+            (a, l): (A, String),
+            (b, r): (B, String),
+        ) -> (C, String) {
+            (f(a, b), l + &r + "zip_with;")
+        }
+
+        pub fn and_then<A, B, F: FnOnce(A) -> (B, String)>(
+            (a, l): (A, String),
+            f: F,
+        ) -> (B, String) {
+            let (b, r) = f(a);
+            (b, l + &r + "and_then;")
+        }
+
+        pub fn then<A, B>((_, l): (A, String), (b, r): (B, String)) -> (B, String) {
+            (b, l + &r + "then;")
+        }
+    }
+}
+
+fn main() {
+    use qualified_do_macro::qdo;
+    use traced::Traced;
+
+    let ans = qdo! {Traced {
+        i <- (1, String::new());
+        j <- (2, String::new());
+        k <- (i + j, String::new());
+        l <- (4, String::new());
+        return i + j + k + l
+    }};
+    assert_eq!(ans, (10, "zip_with;zip_with;fmap;and_then;".to_string()));
+
+    let ans = qdo! {Traced {
+        i <- (1, String::new());
+        j <- (2, String::new());
+        k <- (i * 10, String::new());
+        l <- (j * 10, String::new());
+        return i + j + k + l
+    }};
+    assert_eq!(ans, (33, "zip_with;zip_with;fmap;and_then;".to_string()));
+
+    // Purely sequential blocks are desugared monadically.
+    let ans = qdo! {Traced {
+        i <- (1, String::new());
+        let k = i + 1;
+        j <- (k, String::new());
+        return i + j
+    }};
+    assert_eq!(ans, (3, "and_then;and_then;".to_string()));
+
+    // The last expression is evaluated only after the preceding statements.
+    let ans = qdo! {Traced {
+        i <- (1, String::new());
+        _ <- (i + 1, String::new());
+        (i * 10, String::new())
+    }};
+    assert_eq!(ans, (10, "and_then;and_then;".to_string()));
+
+    let ans = qdo! {Traced {
+        (1, String::new());
+        (2, String::new());
+        (3, String::new())
+    }};
+    assert_eq!(ans, (3, "zip_with;and_then;".to_string()));
+
+    // In applicative mode, the last expression independent of the last group is sequenced with `then`.
+    let ans = qdo! {#[applicative] Traced {
+        (1, String::new());
+        (2, String::new());
+        (3, String::new())
+    }};
+    assert_eq!(ans, (3, "zip_with;then;".to_string()));
+}
@@ -0,0 +1,26 @@
+fn main() {
+    use functo_rs::data::*;
+    use qualified_do_macro::qdo;
+    let is = vec![1, 2, 3];
+    let js = vec![4, 5, 6];
+
+    // `ZipVec` has no `and_then`, so dependent `let`s must be floated into `fmap`.
+    let ans: Vec<i64> = {
+        let is = is.clone();
+        let js = js.clone();
+        qdo! {ZipVec {
+            i <- is;
+            let k = i * 10;
+            j <- js;
+            let (l, m) = (k + j, i);
+            return l + m
+        }}
+    };
+    assert_eq!(
+        ans,
+        is.into_iter()
+            .zip(js)
+            .map(|(i, j)| i * 10 + j + i)
+            .collect::<Vec<_>>()
+    );
+}
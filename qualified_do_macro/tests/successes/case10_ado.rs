@@ -0,0 +1,18 @@
+fn main() {
+    use functo_rs::data::*;
+    use qualified_do_macro::ado;
+    let is = vec![1, 2, 3];
+    let js = vec![(4,), (5,), (6,)];
+
+    let ans: Vec<i64> = {
+        let is = is.clone();
+        let js = js.clone();
+        ado! {ZipVec {
+            i <- is;
+            ~(j,) <- js;
+            let k = i + j;
+            return k * 10
+        }}
+    };
+    assert_eq!(ans, vec![50, 70, 90]);
+}
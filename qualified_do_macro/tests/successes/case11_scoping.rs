@@ -0,0 +1,23 @@
+fn main() {
+    use functo_rs::control::*;
+    use qualified_do_macro::{ado, qdo};
+    type Optioned = AsControl<OptionFunctor>;
+
+    // Variables used only in format strings are dependencies.
+    let ans: Option<String> = qdo! {Optioned {
+        x <- Some(1);
+        y <- Some(format!("{x}!"));
+        return y
+    }};
+    assert_eq!(ans, Some("1!".to_string()));
+
+    // Variables shadowed by closures, inner blocks and match arms are not dependencies.
+    let ans: Option<i64> = ado! {Optioned {
+        x <- Some(1);
+        y <- Some({ let x = 10; x * 2 });
+        z <- Some(1).map(|x| x + 1);
+        w <- match Some(3) { Some(x) => Some(x), None => None };
+        return x + y + z + w
+    }};
+    assert_eq!(ans, Some(26));
+}
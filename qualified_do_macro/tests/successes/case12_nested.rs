@@ -0,0 +1,56 @@
+fn main() {
+    use functo_rs::control::*;
+    use qualified_do_macro::{ado, qdo};
+    type Optioned = AsControl<OptionFunctor>;
+
+    let classify = |n: Option<i64>| -> Option<(i64, &'static str)> {
+        qdo! {Optioned {
+            x <- n;
+            label <- match x {
+                0 => { return "zero" },
+                x if x < 0 => {
+                    guard x > -10;
+                    return "small negative"
+                },
+                _ => {
+                    y <- x.checked_mul(2);
+                    return if y > 100 { "large" } else { "positive" }
+                },
+            };
+            if x == 42 {
+                None::<()>
+            };
+            return (x, label)
+        }}
+    };
+    assert_eq!(classify(Some(0)), Some((0, "zero")));
+    assert_eq!(classify(Some(-3)), Some((-3, "small negative")));
+    assert_eq!(classify(Some(-30)), None);
+    assert_eq!(classify(Some(51)), Some((51, "large")));
+    assert_eq!(classify(Some(42)), None);
+    assert_eq!(classify(None), None);
+
+    let pick = |b: bool| -> Option<i64> {
+        qdo! {#[direct] Optioned {
+            x <- Some(1);
+            y <- if b {
+                z <- Some(10);
+                return x + z
+            } else if x > 0 {
+                Some(x)
+            } else {
+                None
+            };
+            return x + y
+        }}
+    };
+    assert_eq!(pick(true), Some(12));
+    assert_eq!(pick(false), Some(2));
+
+    let ans: Option<i64> = ado! {Optioned {
+        x <- Some(1);
+        y <- if true { z <- Some(2); return z } else { Some(3) };
+        return x + y
+    }};
+    assert_eq!(ans, Some(3));
+}
@@ -0,0 +1,47 @@
+#![deny(warnings)]
+
+fn main() {
+    use functo_rs::control::*;
+    type Optioned = AsControl<OptionFunctor>;
+    type Resulted<E> = AsControl<ResultFunctor<E>>;
+    use qualified_do_macro::qdo;
+
+    // Exhaustive `catch` arms need no rethrow.
+    let ans = |x: Option<i64>| -> Option<i64> {
+        qdo! {Optioned {
+            try {
+                y <- x;
+                return y + 1
+            } catch () => {
+                return 0
+            }
+        }}
+    };
+    assert_eq!(ans(Some(1)), Some(2));
+    assert_eq!(ans(None), Some(0));
+
+    let ans = |x: i64| -> Result<i64, String> {
+        qdo! {#[direct] Resulted {
+            y <- try {
+                guard_even(x);
+                return x / 2
+            } catch e if e.is_empty() => {
+                throw "empty".to_string()
+            };
+            if y > 10 {
+                throw format!("{y} is too large")
+            };
+            return y
+        }}
+    };
+    fn guard_even(x: i64) -> Result<(), String> {
+        if x % 2 == 0 {
+            Ok(())
+        } else {
+            Err(format!("{x} is odd"))
+        }
+    }
+    assert_eq!(ans(4), Ok(2));
+    assert_eq!(ans(3), Err("3 is odd".to_string()));
+    assert_eq!(ans(40), Err("20 is too large".to_string()));
+}
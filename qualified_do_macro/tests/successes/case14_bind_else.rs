@@ -0,0 +1,58 @@
+#![deny(warnings)]
+
+fn main() {
+    use functo_rs::control::*;
+    type Resulted<E> = AsControl<ResultFunctor<E>>;
+    use qualified_do_macro::qdo;
+
+    fn lookup(key: &str) -> Result<Option<i64>, String> {
+        match key {
+            "one" => Ok(Some(1)),
+            "two" => Ok(Some(2)),
+            "none" => Ok(None),
+            _ => Err(format!("unknown key: {key}")),
+        }
+    }
+
+    let ans = |k1: &str, k2: &str| -> Result<i64, String> {
+        qdo! {Resulted {
+            Some(x) <- lookup(k1) else {
+                Err(format!("{k1} is missing"))
+            };
+            Some(y) <- lookup(k2) else { return -1 };
+            let 0.. = x - y else { return 0 };
+            return x - y
+        }}
+    };
+    assert_eq!(ans("two", "one"), Ok(1));
+    assert_eq!(ans("none", "one"), Err("none is missing".to_string()));
+    assert_eq!(ans("one", "none"), Ok(-1));
+    assert_eq!(ans("one", "two"), Ok(0));
+    assert_eq!(ans("three", "one"), Err("unknown key: three".to_string()));
+
+    let ans = |k1: &str, k2: &str| -> Result<i64, String> {
+        qdo! {#[direct] Resulted {
+            Some(x) <- lookup(k1) else {
+                Err(format!("{k1} is missing"))
+            };
+            Some(y) <- lookup(k2) else { return -1 };
+            let 0.. = x - y else { return 0 };
+            return x - y
+        }}
+    };
+    assert_eq!(ans("two", "one"), Ok(1));
+    assert_eq!(ans("none", "one"), Err("none is missing".to_string()));
+    assert_eq!(ans("one", "none"), Ok(-1));
+    assert_eq!(ans("one", "two"), Ok(0));
+
+    let ans = |k1: &str, k2: &str| -> Result<i64, String> {
+        qdo! {#[monadic] Resulted {
+            Some(x) <- lookup(k1) else { return -10 };
+            let Some(y) = lookup(k2).ok().flatten() else { return -20 };
+            return x + y
+        }}
+    };
+    assert_eq!(ans("one", "two"), Ok(3));
+    assert_eq!(ans("none", "two"), Ok(-10));
+    assert_eq!(ans("one", "none"), Ok(-20));
+}
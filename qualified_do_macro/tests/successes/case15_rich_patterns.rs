@@ -0,0 +1,80 @@
+#![deny(warnings)]
+
+fn main() {
+    use functo_rs::control::*;
+    type Optioned = AsControl<OptionFunctor>;
+    type Resulted<E> = AsControl<ResultFunctor<E>>;
+    use qualified_do_macro::qdo;
+
+    #[derive(Clone, Copy)]
+    enum Shape {
+        Square(u32),
+        Rect(u32, u32),
+        Circle(u32),
+    }
+
+    // Or-patterns, pattern guards and type ascriptions.
+    let width = |s: Shape, min: u32| -> Result<u32, String> {
+        qdo! {Resulted {
+            Shape::Square(w) | Shape::Rect(w, _) <- Ok(s);
+            w2 <- Ok(w * 2) if w2 >= min;
+            n: u64 <- "1".parse().map_err(|e: std::num::ParseIntError| e.to_string());
+            return w2 + n as u32
+        }}
+    };
+    assert_eq!(width(Shape::Square(3), 0), Ok(7));
+    assert_eq!(width(Shape::Rect(4, 1), 0), Ok(9));
+    // Drops the location line from the failure message.
+    let unmatched = |r: Result<u32, String>| r.map_err(|e| e.split_once('\n').unwrap().1.to_string());
+    assert_eq!(
+        unmatched(width(Shape::Circle(1), 0)),
+        Err("  expected: Shape :: Square(w) | Shape :: Rect(w, _)\n  scrutinee: Ok(s)".to_string())
+    );
+    assert_eq!(
+        unmatched(width(Shape::Square(3), 10)),
+        Err("  expected: w2 if w2 >= min\n  scrutinee: Ok(w * 2)".to_string())
+    );
+
+    // Guards with `else`, in a group with ascribed binders.
+    let ans = |x: &str, y: &str| -> Option<u32> {
+        qdo! {Optioned {
+            a: u32 <- x.parse().ok();
+            b: u32 <- y.parse().ok() if a < b else { return 0 };
+            return a + b
+        }}
+    };
+    assert_eq!(ans("1", "2"), Some(3));
+    assert_eq!(ans("2", "1"), Some(0));
+    assert_eq!(ans("x", "1"), None);
+
+    // Ascriptions in a group are carried into the joined closure parameter.
+    let sum = |x: &str, y: &str| -> Option<u32> {
+        qdo! {Optioned {
+            a: u32 <- x.parse().ok();
+            b: u32 <- y.parse().ok();
+            return a + b
+        }}
+    };
+    assert_eq!(sum("1", "2"), Some(3));
+
+    // Irrefutable or-patterns.
+    let ans = |r: Result<u32, u32>| -> Option<u32> {
+        qdo! {Optioned {
+            ~(Ok(x) | Err(x)) <- Some(r);
+            return x
+        }}
+    };
+    assert_eq!(ans(Ok(1)), Some(1));
+    assert_eq!(ans(Err(2)), Some(2));
+
+    let ans = |s: Shape| -> Option<u32> {
+        qdo! {#[direct] Optioned {
+            Shape::Square(w) | Shape::Circle(w) <- Some(s) else { return 0 };
+            x: u32 <- Some(w + 1) if x % 2 == 0;
+            return x
+        }}
+    };
+    assert_eq!(ans(Shape::Square(1)), Some(2));
+    assert_eq!(ans(Shape::Circle(2)), None);
+    assert_eq!(ans(Shape::Rect(1, 1)), Some(0));
+}
@@ -0,0 +1,26 @@
+#![deny(warnings)]
+
+fn main() {
+    use functo_rs::control::*;
+    type Optioned = AsControl<OptionFunctor>;
+    use qualified_do_macro::qdo;
+
+    // Nothing to report for fully applicative blocks.
+    let limit = 3;
+    let ans = qdo! {#[explain(strict)] Optioned {
+        x <- Some(1);
+        let y = x + 1;
+        ~(a, b) <- Some((2, 3));
+        guard limit > 0;
+        return x + y + a + b
+    }};
+    assert_eq!(ans, Some(8));
+
+    // `#[explain]` doesn't change the desugaring.
+    let ans = qdo! {#[explain] #[monadic] Optioned {
+        x <- Some(1);
+        y <- Some(x + 1);
+        return x + y
+    }};
+    assert_eq!(ans, Some(3));
+}
@@ -0,0 +1,54 @@
+#![deny(warnings)]
+
+fn main() {
+    use qualified_do_macro::qdo_stringify;
+
+    // The block is only desugared, not type-checked.
+    let applicative = qdo_stringify! {Optioned {
+        x <- Some(1);
+        y <- Some(2);
+        return x + y
+    }};
+    assert_eq!(
+        applicative,
+        "\
+// mode: auto (applicative)
+{
+    Optioned::fmap(
+        |(x, y)| { x + y },
+        Optioned::zip_with(
+            |__qdo_arg_1, __qdo_arg_2| (__qdo_arg_1, __qdo_arg_2),
+            Some(1),
+            Some(2),
+        ),
+    )
+}"
+    );
+
+    let monadic = qdo_stringify! {Optioned {
+        x <- Some(1);
+        Some(y) <- lookup(x);
+        return y
+    }};
+    assert!(monadic.starts_with("// mode: auto (monadic)\nOptioned::and_then(\n"));
+    assert!(monadic.contains("            Some(y) => Optioned::pure(y),\n"));
+
+    let direct = qdo_stringify! {#[direct] Resulted {
+        s <- input.ok_or("no input".to_string());
+        return s.len()
+    }};
+    assert_eq!(
+        direct,
+        "\
+// mode: direct
+'__qdo0: {
+    let s = match Resulted::branch(input.ok_or(\"no input\".to_string())) {
+        ::core::ops::ControlFlow::Continue(v) => v,
+        ::core::ops::ControlFlow::Break(r) => {
+            break '__qdo0 Resulted::from_residual(r);
+        }
+    };
+    Resulted::pure(s.len())
+}"
+    );
+}
@@ -0,0 +1,43 @@
+#![deny(warnings)]
+
+fn main() {
+    use qualified_do_macro::qcomp;
+
+    let xs = vec![1, 2, 3, 4];
+    let pairs: Vec<(i64, i64, i64)> = {
+        use functo_rs::nonlinear::*;
+        qcomp![UndetVec; (x, y, z) | x <- xs.clone(), y <- xs.clone(), guard x < y, let z = x * y]
+    };
+    assert_eq!(
+        pairs,
+        vec![
+            (1, 2, 2),
+            (1, 3, 3),
+            (1, 4, 4),
+            (2, 3, 6),
+            (2, 4, 8),
+            (3, 4, 12)
+        ]
+    );
+
+    // `|` starting a closure does not separate qualifiers.
+    let succs: Vec<i32> = {
+        use functo_rs::nonlinear::*;
+        qcomp![UndetVec; f(x) | x <- vec![1, 2], let f = |a: i32| a + 1]
+    };
+    assert_eq!(succs, vec![2, 3]);
+    let scaled: Vec<i32> = {
+        use functo_rs::nonlinear::*;
+        qcomp![UndetVec; g(x) | x <- vec![1, 2], let k = 10, let g = move |a: i32| a * k | y <- vec![0]]
+    };
+    assert_eq!(scaled, vec![10, 20]);
+
+    use functo_rs::data::*;
+    let sums: Vec<i64> = qcomp![ZipVec; x + y | x <- vec![1, 2, 3] | y <- vec![10, 20, 30]];
+    assert_eq!(sums, vec![11, 22, 33]);
+
+    let triples: Vec<(i64, char, bool)> = qcomp![ZipVec;
+        (x, c, b) | x <- vec![1, 2] | c <- vec!['a', 'b'] | b <- vec![true, false]
+    ];
+    assert_eq!(triples, vec![(1, 'a', true), (2, 'b', false)]);
+}
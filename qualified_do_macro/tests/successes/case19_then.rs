@@ -0,0 +1,56 @@
+#![deny(warnings)]
+
+use functo_rs::nonlinear::*;
+use std::cmp::Ordering;
+
+/// Groups adjacent results only, unlike `UndetVec::group_by`.
+fn group_adjacent<A, F>(mut compare: F, xs: Vec<A>) -> Vec<Vec<A>>
+where
+    F: FnMut(&A, &A) -> Ordering,
+{
+    let mut groups: Vec<Vec<A>> = Vec::new();
+    for x in xs {
+        match groups.last_mut() {
+            Some(group) if compare(&group[0], &x) == Ordering::Equal => group.push(x),
+            _ => groups.push(vec![x]),
+        }
+    }
+    groups
+}
+
+fn main() {
+    use qualified_do_macro::{qcomp, qdo};
+
+    let names = vec!["carol", "alice", "bob", "dave"];
+    let sorted: Vec<(usize, &str)> = qdo! {UndetVec {
+        name <- names.clone();
+        let len = name.len();
+        then sort_by (len, name);
+        then take 3;
+        return (len, name)
+    }};
+    assert_eq!(sorted, vec![(3, "bob"), (4, "dave"), (5, "alice")]);
+
+    let grouped: Vec<(usize, Vec<&str>)> = qdo! {UndetVec {
+        name <- names.clone();
+        let len = name.len();
+        then group by len;
+        return (len[0], name)
+    }};
+    assert_eq!(
+        grouped,
+        vec![(3, vec!["bob"]), (4, vec!["dave"]), (5, vec!["carol", "alice"])]
+    );
+
+    let runs: Vec<(bool, usize)> = qdo! {UndetVec {
+        x <- vec![1, 3, 4, 6, 8, 5];
+        then group by x % 2 == 0 using group_adjacent;
+        return (x[0] % 2 == 0, x.len())
+    }};
+    assert_eq!(runs, vec![(false, 2), (true, 3), (false, 1)]);
+
+    let top: Vec<(i32, i32)> = qcomp![UndetVec;
+        (x, y) | x <- vec![1, 2, 3], y <- vec![1, 2, 3], guard x != y, then sort_by -(x * y), then take 2
+    ];
+    assert_eq!(top, vec![(2, 3), (3, 2)]);
+}
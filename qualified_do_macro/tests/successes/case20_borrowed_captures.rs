@@ -0,0 +1,31 @@
+#![deny(warnings)]
+
+fn main() {
+    use functo_rs::control::*;
+    use functo_rs::data::{Apply as _, Functor as _, ZipVec};
+    use qualified_do_macro::{qcomp, qdo};
+    type Optioned = AsControl<OptionFunctor>;
+
+    // Locals captured by the final `fmap` are borrowed, so they remain usable after the block.
+    let v = vec![1, 2];
+    let r = qdo! {Optioned {
+        x <- Some(1);
+        y <- Some(2);
+        return x + y + v.len()
+    }};
+    assert_eq!(r, Some(5));
+
+    let ws = qcomp![ZipVec; x + y + v.len() | x <- vec![1, 2] | y <- vec![10, 20]];
+    assert_eq!(ws, vec![13, 24]);
+
+    // Variables bound in the block are moved into the continuation instead.
+    let s = qdo! {Optioned {
+        x <- Some(vec![1, 2, 3]);
+        let n = x.len();
+        y <- Some(1);
+        z <- Some(2);
+        return n + y + z + v.len()
+    }};
+    assert_eq!(s, Some(8));
+    println!("{:?}", v);
+}
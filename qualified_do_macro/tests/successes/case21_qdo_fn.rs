@@ -0,0 +1,54 @@
+#![deny(warnings)]
+
+use functo_rs::control::*;
+use qualified_do_macro::qdo_fn;
+type Optioned = AsControl<OptionFunctor>;
+type Resulted<E> = AsControl<ResultFunctor<E>>;
+
+#[qdo_fn(Optioned)]
+fn add(x: Option<i64>, y: Option<i64>) -> Option<i64> {
+    let a = bind!(x);
+    let b: i64 = bind!(y);
+    return a + b
+}
+
+#[qdo_fn(#[monadic] Resulted::<String>)]
+fn halve(x: i64) -> Result<i64, String> {
+    let y = x.abs();
+    if y % 2 == 1 {
+        throw!(format!("{x} is odd"))
+    } else {
+        return y / 2
+    };
+    return x / 2
+}
+
+struct Config(Vec<(&'static str, &'static str)>);
+
+impl Config {
+    fn get(&self, key: &str) -> Option<&'static str> {
+        self.0.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+    }
+
+    #[qdo_fn(Optioned)]
+    fn port(&self) -> Option<u16> {
+        let s = bind!(self.get("port"));
+        let Ok(n) = bind!(Some(s.parse::<u16>())) else {
+            return 0
+        };
+        guard!(n != 1);
+        return n
+    }
+}
+
+fn main() {
+    assert_eq!(add(Some(1), Some(2)), Some(3));
+    assert_eq!(add(Some(1), None), None);
+    assert_eq!(halve(-4), Ok(-2));
+    assert_eq!(halve(3), Err("3 is odd".to_string()));
+    let config = |port| Config(vec![("host", "localhost"), ("port", port)]);
+    assert_eq!(config("8080").port(), Some(8080));
+    assert_eq!(config("http").port(), Some(0));
+    assert_eq!(config("1").port(), None);
+    assert_eq!(Config(vec![]).port(), None);
+}
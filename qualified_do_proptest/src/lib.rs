@@ -1,5 +1,6 @@
 use proptest::prelude::*;
 use proptest::strategy;
+use qualified_do::FailInfo;
 use std::fmt::Debug;
 
 pub enum BoxedProptest {}
@@ -36,7 +37,25 @@ impl BoxedProptest {
     }
 
     #[inline(always)]
-    pub fn and_then<A, B, F>(f: F, fa: BoxedStrategy<A>) -> BoxedStrategy<B>
+    pub fn then<A, B>(fa: BoxedStrategy<A>, fb: BoxedStrategy<B>) -> BoxedStrategy<B>
+    where
+        A: Debug + 'static,
+        B: Debug + 'static,
+    {
+        (fa, fb).prop_map(|(_, b)| b).boxed()
+    }
+
+    #[inline(always)]
+    pub fn then_left<A, B>(fa: BoxedStrategy<A>, fb: BoxedStrategy<B>) -> BoxedStrategy<A>
+    where
+        A: Debug + 'static,
+        B: Debug + 'static,
+    {
+        (fa, fb).prop_map(|(a, _)| a).boxed()
+    }
+
+    #[inline(always)]
+    pub fn and_then<A, B, F>(fa: BoxedStrategy<A>, f: F) -> BoxedStrategy<B>
     where
         A: Debug + 'static,
         B: Debug,
@@ -51,6 +70,11 @@ impl BoxedProptest {
             .prop_filter_map(msg.to_string(), |i| i)
             .boxed()
     }
+
+    #[inline(always)]
+    pub fn fail_with<T: Clone + Debug + 'static>(info: FailInfo) -> BoxedStrategy<T> {
+        Self::fail(&info.to_string())
+    }
 }
 
 type Mapped<S, T, A, B, C> = strategy::Map<(S, T), Box<dyn Fn((A, B)) -> C + 'static>>;
@@ -88,7 +112,29 @@ impl Proptest {
     }
 
     #[inline(always)]
-    pub fn and_then<A, B, S, T, F>(f: F, fa: S) -> strategy::Flatten<strategy::Map<S, F>>
+    pub fn then<A, B, S, T>(fa: S, fb: T) -> impl Strategy<Value = B>
+    where
+        A: Debug,
+        B: Debug,
+        S: Strategy<Value = A>,
+        T: Strategy<Value = B>,
+    {
+        (fa, fb).prop_map(|(_, b)| b)
+    }
+
+    #[inline(always)]
+    pub fn then_left<A, B, S, T>(fa: S, fb: T) -> impl Strategy<Value = A>
+    where
+        A: Debug,
+        B: Debug,
+        S: Strategy<Value = A>,
+        T: Strategy<Value = B>,
+    {
+        (fa, fb).prop_map(|(a, _)| a)
+    }
+
+    #[inline(always)]
+    pub fn and_then<A, B, S, T, F>(fa: S, f: F) -> strategy::Flatten<strategy::Map<S, F>>
     where
         A: Debug + 'static,
         B: Debug,
@@ -103,6 +149,11 @@ impl Proptest {
     pub fn fail<T: Clone + Debug>(msg: &str) -> impl Strategy<Value = T> {
         Just(Option::<T>::None).prop_filter_map(msg.to_string(), |i| i)
     }
+
+    #[inline(always)]
+    pub fn fail_with<T: Clone + Debug>(info: FailInfo) -> impl Strategy<Value = T> {
+        Self::fail(&info.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -136,10 +187,25 @@ mod tests {
         })
     }
 
+    fn gen_sized_vec() -> impl Strategy<Value = (usize, Vec<i32>, bool)> {
+        use qualified_do::qdo;
+        qdo! { BoxedProptest {
+            n <- (0usize..10).boxed();
+            b <- any::<bool>().boxed();
+            xs <- proptest::collection::vec(any::<i32>(), n).boxed();
+            return (n, xs, b)
+        }}
+    }
+
     proptest! {
         #[test]
         fn test_gen_expr(expr in gen_expr()) {
             println!("{:?}", expr);
         }
+
+        #[test]
+        fn test_gen_sized_vec((n, xs, _) in gen_sized_vec()) {
+            prop_assert_eq!(n, xs.len());
+        }
     }
 }